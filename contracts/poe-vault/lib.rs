@@ -0,0 +1,253 @@
+//! A contract notarizing claims on behalf of its callers.
+//!
+//! Claims are created through the runtime's proof-of-existence chain extension, so on chain
+//! they are owned by the vault. The vault remembers who asked for each claim.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_env::{AccountId, DefaultEnvironment, Environment};
+use ink_lang as ink;
+use ink_prelude::vec::Vec;
+
+type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+
+/// The proof-of-existence chain extension of the runtime, see `runtime/src/chain_extension.rs`.
+#[ink::chain_extension]
+pub trait Poe {
+	type ErrorCode = PoeError;
+
+	/// The owner of `claim` and the block it was created in, if it is claimed.
+	#[ink(extension = 1, handle_status = false, returns_result = false)]
+	fn proof_of(claim: Vec<u8>) -> Option<(AccountId, BlockNumber)>;
+
+	/// Claim `claim` for the calling contract.
+	#[ink(extension = 2, returns_result = false)]
+	fn create_claim(claim: Vec<u8>);
+
+	/// Whether `account` owns `claim`.
+	#[ink(extension = 3, handle_status = false, returns_result = false)]
+	fn is_owner(claim: Vec<u8>, account: AccountId) -> bool;
+}
+
+/// Why the chain extension refused to create a claim.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PoeError {
+	/// The claim is already taken.
+	AlreadyClaimed,
+	/// The claim is shorter than allowed.
+	TooShort,
+	/// The claim is longer than allowed.
+	TooLong,
+	/// Any other failure.
+	Other,
+}
+
+impl ink_env::chain_extension::FromStatusCode for PoeError {
+	fn from_status_code(status_code: u32) -> Result<(), Self> {
+		match status_code {
+			0 => Ok(()),
+			1 => Err(Self::AlreadyClaimed),
+			2 => Err(Self::TooShort),
+			3 => Err(Self::TooLong),
+			_ => Err(Self::Other),
+		}
+	}
+}
+
+impl From<scale::Error> for PoeError {
+	fn from(_: scale::Error) -> Self {
+		panic!("encountered unexpected invalid SCALE encoding")
+	}
+}
+
+/// The default environment, with the proof-of-existence chain extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PoeEnvironment {}
+
+impl Environment for PoeEnvironment {
+	const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+	type AccountId = <DefaultEnvironment as Environment>::AccountId;
+	type Balance = <DefaultEnvironment as Environment>::Balance;
+	type Hash = <DefaultEnvironment as Environment>::Hash;
+	type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+	type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+	type RentFraction = <DefaultEnvironment as Environment>::RentFraction;
+
+	type ChainExtension = Poe;
+}
+
+#[ink::contract(env = crate::PoeEnvironment)]
+mod poe_vault {
+	use super::PoeError;
+	use ink_prelude::vec::Vec;
+	use ink_storage::collections::HashMap as StorageHashMap;
+
+	#[ink(storage)]
+	pub struct PoeVault {
+		/// Who asked for each claim created by the vault.
+		requesters: StorageHashMap<Vec<u8>, AccountId>,
+	}
+
+	/// A claim was created on behalf of `requester`.
+	#[ink(event)]
+	pub struct Notarized {
+		#[ink(topic)]
+		requester: AccountId,
+		claim: Vec<u8>,
+	}
+
+	impl PoeVault {
+		#[ink(constructor)]
+		pub fn new() -> Self {
+			Self { requesters: Default::default() }
+		}
+
+		/// Claim `claim` for the vault on behalf of the caller.
+		#[ink(message)]
+		pub fn notarize(&mut self, claim: Vec<u8>) -> Result<(), PoeError> {
+			self.env().extension().create_claim(claim.clone())?;
+			let requester = self.env().caller();
+			self.requesters.insert(claim.clone(), requester);
+			self.env().emit_event(Notarized { requester, claim });
+			Ok(())
+		}
+
+		/// The on-chain owner of `claim` and the block it was created in.
+		#[ink(message)]
+		pub fn proof_of(&self, claim: Vec<u8>) -> Option<(AccountId, BlockNumber)> {
+			self.env().extension().proof_of(claim)
+		}
+
+		/// Whether the vault owns `claim` on chain.
+		#[ink(message)]
+		pub fn holds(&self, claim: Vec<u8>) -> bool {
+			let vault = self.env().account_id();
+			self.env().extension().is_owner(claim, vault)
+		}
+
+		/// Who asked the vault for `claim`.
+		#[ink(message)]
+		pub fn requester_of(&self, claim: Vec<u8>) -> Option<AccountId> {
+			self.requesters.get(&claim).copied()
+		}
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+		use ink_env::DefaultEnvironment;
+		use ink_lang as ink;
+		use scale::{Decode, Encode};
+		use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+		type Proofs = Rc<RefCell<BTreeMap<Vec<u8>, (AccountId, BlockNumber)>>>;
+
+		/// The claim length limits of the runtime, `MinPoeLength` and `MaxPoeLength`.
+		const MIN_LENGTH: usize = 8;
+		const MAX_LENGTH: usize = 32;
+
+		/// Mimics one function of the runtime's chain extension over shared `proofs`.
+		struct MockPoe {
+			func_id: u32,
+			proofs: Proofs,
+			vault: AccountId,
+		}
+
+		impl ink_env::test::ChainExtension for MockPoe {
+			fn func_id(&self) -> u32 {
+				self.func_id
+			}
+
+			fn call(&mut self, mut input: &[u8], output: &mut Vec<u8>) -> u32 {
+				let mut proofs = self.proofs.borrow_mut();
+				match self.func_id {
+					1 => {
+						let claim = Vec::<u8>::decode(&mut input).unwrap();
+						proofs.get(&claim).cloned().encode_to(output);
+					},
+					2 => {
+						let claim = Vec::<u8>::decode(&mut input).unwrap();
+						if claim.len() < MIN_LENGTH {
+							return 2
+						}
+						if claim.len() > MAX_LENGTH {
+							return 3
+						}
+						if proofs.contains_key(&claim) {
+							return 1
+						}
+						proofs.insert(claim, (self.vault, 1));
+					},
+					3 => {
+						let (claim, account) = <(Vec<u8>, AccountId)>::decode(&mut input).unwrap();
+						proofs
+							.get(&claim)
+							.map_or(false, |(owner, _)| *owner == account)
+							.encode_to(output);
+					},
+					_ => unreachable!(),
+				}
+				0
+			}
+		}
+
+		/// Register the mocked chain extension and return the claims it holds.
+		fn register_poe() -> Proofs {
+			let proofs = Proofs::default();
+			let vault = ink_env::account_id::<DefaultEnvironment>().unwrap();
+			for func_id in 1..=3 {
+				ink_env::test::register_chain_extension(MockPoe {
+					func_id,
+					proofs: proofs.clone(),
+					vault,
+				});
+			}
+			proofs
+		}
+
+		fn alice() -> AccountId {
+			ink_env::test::default_accounts::<DefaultEnvironment>().unwrap().alice
+		}
+
+		#[ink::test]
+		fn notarize_claims_for_the_vault() {
+			let proofs = register_poe();
+			let mut vault = PoeVault::new();
+
+			assert_eq!(vault.notarize(b"hello world".to_vec()), Ok(()));
+
+			let owner = ink_env::account_id::<DefaultEnvironment>().unwrap();
+			assert_eq!(proofs.borrow().get(&b"hello world".to_vec()), Some(&(owner, 1)));
+			assert_eq!(vault.proof_of(b"hello world".to_vec()), Some((owner, 1)));
+			assert!(vault.holds(b"hello world".to_vec()));
+			assert_eq!(vault.requester_of(b"hello world".to_vec()), Some(alice()));
+			assert_eq!(ink_env::test::recorded_events().count(), 1);
+		}
+
+		#[ink::test]
+		fn notarize_fails_for_taken_claims() {
+			register_poe();
+			let mut vault = PoeVault::new();
+
+			assert_eq!(vault.notarize(b"hello world".to_vec()), Ok(()));
+			assert_eq!(vault.notarize(b"hello world".to_vec()), Err(PoeError::AlreadyClaimed));
+			assert_eq!(vault.notarize(b"hi".to_vec()), Err(PoeError::TooShort));
+			assert_eq!(vault.notarize(vec![0; 33]), Err(PoeError::TooLong));
+			assert_eq!(vault.requester_of(b"hi".to_vec()), None);
+		}
+
+		#[ink::test]
+		fn unknown_claims_are_not_held() {
+			let proofs = register_poe();
+			let vault = PoeVault::new();
+			proofs.borrow_mut().insert(b"hello world".to_vec(), (alice(), 7));
+
+			assert_eq!(vault.proof_of(b"hello world".to_vec()), Some((alice(), 7)));
+			assert!(!vault.holds(b"hello world".to_vec()));
+			assert_eq!(vault.proof_of(b"unknown".to_vec()), None);
+		}
+	}
+}
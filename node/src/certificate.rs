@@ -0,0 +1,66 @@
+//! Offline verification of claim certificates produced by `poe_certificate`.
+
+use codec::Decode;
+use node_template_runtime::{AccountId, BlockNumber, Hash, Header, TemplateModule};
+use sc_cli::Result;
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// A claim certificate as produced by this runtime.
+pub type Certificate = pallet_template_rpc::PoeCertificate<AccountId, BlockNumber, Header>;
+
+/// The `verify-certificate` command used to check a claim certificate offline.
+#[derive(Debug, StructOpt)]
+pub struct VerifyCertificateCmd {
+	/// Path to the certificate, as returned by the `poe_certificate` RPC.
+	#[structopt(parse(from_os_str))]
+	pub certificate: PathBuf,
+
+	/// Hash of a finalized block the verifier trusts.
+	///
+	/// The certificate must have been produced at exactly this block.
+	#[structopt(long, value_name = "HASH")]
+	pub trusted_hash: Hash,
+
+	/// Read the certificate as hex-encoded SCALE instead of JSON.
+	#[structopt(long)]
+	pub scale: bool,
+}
+
+impl VerifyCertificateCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let raw = fs::read_to_string(&self.certificate)?;
+		let certificate: Certificate = if self.scale {
+			let bytes = sp_core::bytes::from_hex(raw.trim())
+				.map_err(|e| format!("Invalid certificate hex: {:?}", e))?;
+			Decode::decode(&mut &bytes[..])?
+		} else {
+			serde_json::from_str(&raw).map_err(|e| format!("Invalid certificate JSON: {}", e))?
+		};
+
+		let expected_key = TemplateModule::proof_key(&certificate.hash);
+		certificate
+			.verify(&self.trusted_hash, &expected_key)
+			.map_err(|e| format!("Certificate rejected: {}", e))?;
+
+		let hash = sp_core::hexdisplay::HexDisplay::from(&certificate.hash);
+		match &certificate.claim {
+			Some((owner, block)) => {
+				println!(
+					"Valid: 0x{} is claimed by {} since block #{} (proven at block #{}, {:?}).",
+					hash, owner, block, certificate.header.number, certificate.block_hash,
+				);
+				if let Some(name) = &certificate.owner_identity {
+					println!("The owner's identity was reported as \"{}\" (not proven).", name);
+				}
+			},
+			None => println!(
+				"Valid: 0x{} is not claimed at block #{} ({:?}).",
+				hash, certificate.header.number, certificate.block_hash,
+			),
+		}
+
+		Ok(())
+	}
+}
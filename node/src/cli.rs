@@ -1,4 +1,5 @@
 use sc_cli::RunCmd;
+use std::{path::PathBuf, str::FromStr};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -8,6 +9,49 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Seal blocks with manual seal instead of Aura and GRANDPA.
+	///
+	/// One of `instant`, `manual` or `interval=<ms>`. Blocks can always be sealed on demand
+	/// through the `engine_createBlock` and `engine_finalizeBlock` RPCs.
+	#[structopt(long, value_name = "MODE")]
+	pub sealing: Option<Sealing>,
+
+	/// Index proof-of-existence events into an SQLite database at this path.
+	///
+	/// The index is served by the `poe_queryEvents` and `poe_queryStatus` RPCs. It is built from
+	/// the state of every block since genesis, so it needs `--pruning archive`.
+	#[structopt(long, value_name = "PATH")]
+	pub poe_index_db: Option<PathBuf>,
+}
+
+/// How blocks are sealed in manual seal mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sealing {
+	/// Seal a block as soon as a transaction enters the pool.
+	Instant,
+	/// Seal blocks only on `engine_createBlock`.
+	Manual,
+	/// Seal a block every given number of milliseconds.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Sealing::Instant),
+			"manual" => Ok(Sealing::Manual),
+			_ => match s.strip_prefix("interval=").map(str::parse) {
+				Some(Ok(millis)) if millis > 0 => Ok(Sealing::Interval(millis)),
+				_ => Err(format!(
+					"Invalid sealing mode {:?}, expected `instant`, `manual` or `interval=<ms>`",
+					s
+				)),
+			},
+		}
+	}
 }
 
 #[derive(Debug, StructOpt)]
@@ -17,6 +61,9 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Generate a raw chain specification from a network description file.
+	GenerateSpec(crate::generate_spec::GenerateSpecCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -35,6 +82,15 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Serve the keys of a local keystore to nodes using it as their remote keystore.
+	Signer(crate::remote_keystore::SignerCmd),
+
+	/// Hash files and claim, revoke or look them up on a running node, or export all claims.
+	Poe(crate::poe::PoeCmd),
+
+	/// Verify a claim certificate offline against a trusted block hash.
+	VerifyCertificate(crate::certificate::VerifyCertificateCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
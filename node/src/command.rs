@@ -18,11 +18,13 @@
 use crate::{
 	chain_spec,
 	cli::{Cli, Subcommand},
+	poe::PoeCmd,
 	service,
 };
 use node_template_runtime::Block;
 use sc_cli::{ChainSpec, Role, RuntimeVersion, SubstrateCli};
 use sc_service::PartialComponents;
+use sp_core::crypto::Ss58AddressFormat;
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -67,12 +69,18 @@ impl SubstrateCli for Cli {
 pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
 
+	// Format addresses with this chain's prefix unless a command asks otherwise.
+	sp_core::crypto::set_default_ss58_version(Ss58AddressFormat::from(u16::from(
+		node_template_runtime::SS58_PREFIX,
+	)));
+
 	match &cli.subcommand {
 		Some(Subcommand::Key(cmd)) => cmd.run(&cli),
 		Some(Subcommand::BuildSpec(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
 		},
+		Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -115,6 +123,16 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
+		Some(Subcommand::Signer(cmd)) => cmd.run(),
+		Some(Subcommand::Poe(PoeCmd::Export(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
+		Some(Subcommand::Poe(PoeCmd::Client(cmd))) => cmd.run(),
+		Some(Subcommand::VerifyCertificate(cmd)) => cmd.run(),
 		Some(Subcommand::Benchmark(cmd)) =>
 			if cfg!(feature = "runtime-benchmarks") {
 				let runner = cli.create_runner(cmd)?;
@@ -127,10 +145,16 @@ pub fn run() -> sc_cli::Result<()> {
 			},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let sealing = cli.sealing;
+			let poe_index_db = cli.poe_index_db;
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
+					Role::Light if sealing.is_some() =>
+						return Err("Manual sealing is not supported on light clients.".into()),
+					Role::Light if poe_index_db.is_some() =>
+						return Err("The claim index is not supported on light clients.".into()),
 					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+					_ => service::new_full(config, sealing, poe_index_db),
 				}
 				.map_err(sc_cli::Error::Service)
 			})
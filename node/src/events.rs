@@ -0,0 +1,24 @@
+//! The runtime events deposited in a block, read from its storage.
+
+use codec::Decode;
+use node_template_runtime::{opaque::Block, Event, Hash};
+use sc_client_api::{Backend, StorageProvider};
+use sp_core::{storage::StorageKey, twox_128};
+use sp_runtime::generic::BlockId;
+
+type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// The events deposited in block `hash`.
+pub fn block_events<C, BA>(client: &C, hash: Hash) -> sp_blockchain::Result<Vec<Event>>
+where
+	C: StorageProvider<Block, BA>,
+	BA: Backend<Block>,
+{
+	let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+	let records = match client.storage(&BlockId::Hash(hash), &key)? {
+		Some(value) => Vec::<EventRecord>::decode(&mut &value.0[..])
+			.map_err(|e| sp_blockchain::Error::Backend(format!("Invalid events: {}", e)))?,
+		None => Vec::new(),
+	};
+	Ok(records.into_iter().map(|record| record.event).collect())
+}
@@ -0,0 +1,470 @@
+//! The `poe export` command, dumping every claim of the registry at a given block.
+//!
+//! Claims are read straight from the node's database by walking the keys of `Proofs`, along with
+//! the original timestamp of imported claims, and are written one by one, so the registry never has
+//! to fit in memory.
+
+use codec::Decode;
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, TemplateModule};
+use parquet::{
+	column::writer::ColumnWriter,
+	data_type::ByteArray,
+	file::{
+		properties::WriterProperties,
+		writer::{FileWriter, RowGroupWriter, SerializedFileWriter},
+	},
+	schema::parser::parse_message_type,
+};
+use sc_cli::{
+	BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, Result, SharedParams,
+};
+use sc_client_api::{Backend, StorageProvider};
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, hexdisplay::HexDisplay, storage::StorageKey};
+use sp_runtime::generic::BlockId;
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+use structopt::StructOpt;
+
+/// The number of claims per Parquet row group, which are buffered before being written.
+const ROW_GROUP_SIZE: usize = 64 * 1024;
+
+/// The Parquet schema of exported claims.
+const PARQUET_SCHEMA: &str = "message claim {
+	REQUIRED BYTE_ARRAY hash (UTF8);
+	REQUIRED BYTE_ARRAY owner (UTF8);
+	REQUIRED INT32 block (UINT_32);
+	OPTIONAL INT64 original_timestamp (TIMESTAMP_MILLIS);
+}";
+
+/// The output format of `poe export`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+	/// A `hash,owner,block,original_timestamp` header followed by one row per claim.
+	Csv,
+	/// A JSON array of `{"hash", "owner", "block", "original_timestamp"}` objects.
+	Json,
+	/// A Parquet file with `hash`, `owner`, `block` and `original_timestamp` columns.
+	Parquet,
+}
+
+impl FromStr for ExportFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"csv" => Ok(ExportFormat::Csv),
+			"json" => Ok(ExportFormat::Json),
+			"parquet" => Ok(ExportFormat::Parquet),
+			_ => Err(format!("Invalid format {:?}, expected `csv`, `json` or `parquet`", s)),
+		}
+	}
+}
+
+/// The `poe export` command.
+#[derive(Debug, StructOpt)]
+pub struct ExportCmd {
+	/// Export the claims as of this block number or hash. Defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// One of `csv`, `json` or `parquet`.
+	#[structopt(long, value_name = "FORMAT", default_value = "csv")]
+	pub format: ExportFormat,
+
+	/// Write to this file instead of stdout. Required for Parquet.
+	#[structopt(long, short, parse(from_os_str), value_name = "PATH")]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// A claim as exported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportedClaim {
+	/// The claimed hash, hex encoded with a `0x` prefix.
+	pub hash: String,
+	/// The SS58 address of the owner.
+	pub owner: String,
+	/// The block the claim dates from.
+	pub block: BlockNumber,
+	/// For imported claims, when they were originally made, in milliseconds since the Unix epoch.
+	pub original_timestamp: Option<u64>,
+}
+
+impl ExportedClaim {
+	/// The claim of a raw `Proofs` key.
+	pub fn claim_of_key(key: &[u8]) -> std::result::Result<Vec<u8>, String> {
+		// Skip the storage prefix and the `Blake2_128` hash preceding the claim.
+		key.get(32 + 16..)
+			.and_then(|mut encoded| Vec::<u8>::decode(&mut encoded).ok())
+			.ok_or_else(|| format!("Invalid claim key 0x{}", HexDisplay::from(&key)))
+	}
+
+	/// Decode the raw `Proofs` value of `claim`, and its raw `ImportedTimestamps` value if any.
+	pub fn decode(
+		claim: &[u8],
+		value: &[u8],
+		original_timestamp: Option<&[u8]>,
+	) -> std::result::Result<Self, String> {
+		let invalid =
+			|e: codec::Error| format!("Invalid claim 0x{}: {}", HexDisplay::from(&claim), e);
+		let (owner, block) = <(AccountId, BlockNumber)>::decode(&mut &value[..]).map_err(invalid)?;
+		let original_timestamp = original_timestamp
+			.map(|mut timestamp| u64::decode(&mut timestamp))
+			.transpose()
+			.map_err(invalid)?;
+		Ok(ExportedClaim {
+			hash: format!("0x{}", HexDisplay::from(&claim)),
+			owner: owner.to_ss58check(),
+			block,
+			original_timestamp,
+		})
+	}
+}
+
+impl ExportCmd {
+	/// Run the command against the database of `client`.
+	pub fn run<C, BA>(&self, client: Arc<C>) -> Result<()>
+	where
+		C: StorageProvider<Block, BA> + HeaderBackend<Block>,
+		BA: Backend<Block>,
+	{
+		let at = match &self.at {
+			Some(at) => at.parse::<Block>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		let hash = client
+			.block_hash_from_id(&at)?
+			.ok_or_else(|| format!("Unknown block {:?}", at))?;
+		let id = BlockId::Hash(hash);
+
+		let mut sink = self.sink()?;
+		let prefix = StorageKey(TemplateModule::proofs_prefix().to_vec());
+		let mut exported = 0u64;
+		for key in client.storage_keys_iter(&id, Some(&prefix), None)? {
+			// The key was just listed at this very block, so the value is there.
+			let value = client
+				.storage(&id, &key)?
+				.ok_or_else(|| format!("Missing value of 0x{}", HexDisplay::from(&key.0)))?;
+			let claim = ExportedClaim::claim_of_key(&key.0)?;
+			let timestamp_key = StorageKey(TemplateModule::imported_timestamp_key(&claim));
+			let timestamp = client.storage(&id, &timestamp_key)?;
+			let timestamp = timestamp.as_ref().map(|data| &data.0[..]);
+			sink.write(&ExportedClaim::decode(&claim, &value.0, timestamp)?)?;
+			exported += 1;
+		}
+		sink.finish()?;
+
+		eprintln!("Exported {} claims as of block {:?}.", exported, hash);
+		Ok(())
+	}
+
+	fn sink(&self) -> Result<Box<dyn ClaimSink>> {
+		if self.format == ExportFormat::Parquet {
+			let path = self.output.as_ref().ok_or("Parquet needs an `--output` file")?;
+			return Ok(Box::new(ParquetSink::new(File::create(path)?)?))
+		}
+
+		let output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+		Ok(match self.format {
+			ExportFormat::Json => Box::new(JsonSink::new(output)),
+			_ => Box::new(CsvSink::new(output)?),
+		})
+	}
+}
+
+impl CliConfiguration for ExportCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Where exported claims are written to.
+pub trait ClaimSink {
+	/// Write one claim.
+	fn write(&mut self, claim: &ExportedClaim) -> Result<()>;
+
+	/// Write whatever remains and close the output.
+	fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Writes claims as CSV.
+pub struct CsvSink<W: Write>(BufWriter<W>);
+
+impl<W: Write> CsvSink<W> {
+	/// Write the header to `output`.
+	pub fn new(output: W) -> Result<Self> {
+		let mut output = BufWriter::new(output);
+		writeln!(output, "hash,owner,block,original_timestamp")?;
+		Ok(CsvSink(output))
+	}
+}
+
+impl<W: Write> ClaimSink for CsvSink<W> {
+	fn write(&mut self, claim: &ExportedClaim) -> Result<()> {
+		// Claims that were not imported have an empty `original_timestamp`.
+		let timestamp = claim.original_timestamp.map(|t| t.to_string()).unwrap_or_default();
+		writeln!(self.0, "{},{},{},{}", claim.hash, claim.owner, claim.block, timestamp)?;
+		Ok(())
+	}
+
+	fn finish(mut self: Box<Self>) -> Result<()> {
+		Ok(self.0.flush()?)
+	}
+}
+
+/// Writes claims as a JSON array.
+pub struct JsonSink<W: Write> {
+	output: BufWriter<W>,
+	first: bool,
+}
+
+impl<W: Write> JsonSink<W> {
+	/// Write to `output`.
+	pub fn new(output: W) -> Self {
+		JsonSink { output: BufWriter::new(output), first: true }
+	}
+}
+
+impl<W: Write> ClaimSink for JsonSink<W> {
+	fn write(&mut self, claim: &ExportedClaim) -> Result<()> {
+		self.output.write_all(if self.first { b"[\n" } else { b",\n" })?;
+		self.first = false;
+		serde_json::to_writer(&mut self.output, claim)
+			.map_err(|e| format!("Could not write claim: {}", e))?;
+		Ok(())
+	}
+
+	fn finish(mut self: Box<Self>) -> Result<()> {
+		self.output.write_all(if self.first { b"[]\n" } else { b"\n]\n" })?;
+		Ok(self.output.flush()?)
+	}
+}
+
+/// Writes claims to a Parquet file, one row group per `ROW_GROUP_SIZE` claims.
+pub struct ParquetSink {
+	writer: SerializedFileWriter<File>,
+	rows: Vec<ExportedClaim>,
+}
+
+impl ParquetSink {
+	/// Write to `file`.
+	pub fn new(file: File) -> Result<Self> {
+		let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(parquet_error)?);
+		let properties = Arc::new(WriterProperties::builder().build());
+		let writer = SerializedFileWriter::new(file, schema, properties).map_err(parquet_error)?;
+		Ok(ParquetSink { writer, rows: Vec::with_capacity(ROW_GROUP_SIZE) })
+	}
+
+	/// Write the buffered claims as a row group.
+	fn flush(&mut self) -> Result<()> {
+		if self.rows.is_empty() {
+			return Ok(())
+		}
+		let mut row_group = self.writer.next_row_group().map_err(parquet_error)?;
+		let mut column = 0;
+		while let Some(mut writer) = row_group.next_column().map_err(parquet_error)? {
+			match (&mut writer, column) {
+				(ColumnWriter::ByteArrayColumnWriter(writer), 0 | 1) => {
+					let values = self
+						.rows
+						.iter()
+						.map(|claim| if column == 0 { &claim.hash } else { &claim.owner })
+						.map(|value| ByteArray::from(value.as_str()))
+						.collect::<Vec<_>>();
+					writer.write_batch(&values, None, None).map_err(parquet_error)?;
+				},
+				(ColumnWriter::Int32ColumnWriter(writer), 2) => {
+					// Block numbers are stored as `UINT_32` in the bits of an `INT32`.
+					let values =
+						self.rows.iter().map(|claim| claim.block as i32).collect::<Vec<_>>();
+					writer.write_batch(&values, None, None).map_err(parquet_error)?;
+				},
+				(ColumnWriter::Int64ColumnWriter(writer), 3) => {
+					// Only the timestamps of imported claims are written, the definition levels
+					// telling which rows they belong to.
+					let values = self
+						.rows
+						.iter()
+						.filter_map(|claim| claim.original_timestamp.map(|t| t as i64))
+						.collect::<Vec<_>>();
+					let levels = self
+						.rows
+						.iter()
+						.map(|claim| claim.original_timestamp.is_some() as i16)
+						.collect::<Vec<_>>();
+					writer.write_batch(&values, Some(&levels), None).map_err(parquet_error)?;
+				},
+				_ => return Err("Unexpected Parquet column".into()),
+			}
+			row_group.close_column(writer).map_err(parquet_error)?;
+			column += 1;
+		}
+		self.writer.close_row_group(row_group).map_err(parquet_error)?;
+		self.rows.clear();
+		Ok(())
+	}
+}
+
+impl ClaimSink for ParquetSink {
+	fn write(&mut self, claim: &ExportedClaim) -> Result<()> {
+		self.rows.push(claim.clone());
+		if self.rows.len() == ROW_GROUP_SIZE {
+			self.flush()?;
+		}
+		Ok(())
+	}
+
+	fn finish(mut self: Box<Self>) -> Result<()> {
+		self.flush()?;
+		self.writer.close().map_err(parquet_error)?;
+		Ok(())
+	}
+}
+
+fn parquet_error(e: parquet::errors::ParquetError) -> sc_cli::Error {
+	format!("Could not write Parquet: {}", e).into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use parquet::{
+		file::reader::{FileReader, SerializedFileReader},
+		record::RowAccessor,
+	};
+
+	fn claims() -> Vec<ExportedClaim> {
+		vec![
+			ExportedClaim {
+				hash: "0x0102".into(),
+				owner: "alice".into(),
+				block: 1,
+				original_timestamp: None,
+			},
+			ExportedClaim {
+				hash: "0x0304".into(),
+				owner: "bob".into(),
+				block: 0,
+				original_timestamp: Some(1_500_000_000_000),
+			},
+		]
+	}
+
+	/// Writes claims to a buffer that is still readable once the sink is finished.
+	#[derive(Clone, Default)]
+	struct Shared(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+	impl Write for Shared {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.borrow_mut().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	fn export(sink: impl FnOnce(Shared) -> Box<dyn ClaimSink>, claims: &[ExportedClaim]) -> String {
+		let output = Shared::default();
+		let mut sink = sink(output.clone());
+		for claim in claims {
+			sink.write(claim).unwrap();
+		}
+		sink.finish().unwrap();
+		let bytes = output.0.borrow().clone();
+		String::from_utf8(bytes).unwrap()
+	}
+
+	#[test]
+	fn proofs_entries_are_decoded() {
+		let owner = AccountId::from([7; 32]);
+		let claim = vec![1u8, 2, 3];
+		let key = [&[0; 32][..], &[9; 16][..], &claim.encode()[..]].concat();
+		let value = (owner.clone(), 42 as BlockNumber).encode();
+		let timestamp = 1_500_000_000_000u64.encode();
+
+		assert_eq!(ExportedClaim::claim_of_key(&key).unwrap(), claim);
+		assert!(ExportedClaim::claim_of_key(&key[..40]).is_err());
+		assert_eq!(
+			ExportedClaim::decode(&claim, &value, None).unwrap(),
+			ExportedClaim {
+				hash: "0x010203".into(),
+				owner: owner.to_ss58check(),
+				block: 42,
+				original_timestamp: None,
+			}
+		);
+		assert_eq!(
+			ExportedClaim::decode(&claim, &value, Some(&timestamp)).unwrap().original_timestamp,
+			Some(1_500_000_000_000)
+		);
+		assert!(ExportedClaim::decode(&claim, &value[..8], None).is_err());
+		assert!(ExportedClaim::decode(&claim, &value, Some(&timestamp[..4])).is_err());
+	}
+
+	#[test]
+	fn claims_are_exported_as_csv() {
+		let csv = export(|out| Box::new(CsvSink::new(out).unwrap()), &claims());
+		assert_eq!(
+			csv,
+			"hash,owner,block,original_timestamp\n0x0102,alice,1,\n0x0304,bob,0,1500000000000\n"
+		);
+	}
+
+	#[test]
+	fn claims_are_exported_as_json() {
+		let json = export(|out| Box::new(JsonSink::new(out)), &claims());
+		let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, serde_json::to_value(claims()).unwrap());
+
+		let empty = export(|out| Box::new(JsonSink::new(out)), &[]);
+		assert_eq!(empty, "[]\n");
+	}
+
+	#[test]
+	fn claims_are_exported_as_parquet() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("claims.parquet");
+		let mut sink = Box::new(ParquetSink::new(File::create(&path).unwrap()).unwrap());
+		for claim in claims() {
+			sink.write(&claim).unwrap();
+		}
+		sink.finish().unwrap();
+
+		let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+		assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+		let rows = reader.get_row_iter(None).unwrap().collect::<Vec<_>>();
+		assert!(rows[0].get_timestamp_millis(3).is_err());
+		assert_eq!(rows[1].get_timestamp_millis(3).unwrap(), 1_500_000_000_000);
+	}
+}
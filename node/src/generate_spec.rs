@@ -0,0 +1,259 @@
+//! The `generate-spec` command, building a raw chain spec from a network description file.
+
+use crate::chain_spec::{self, ChainSpec};
+use node_template_runtime::{AccountId, Balance, BlockNumber, SS58_PREFIX, WASM_BINARY};
+use sc_cli::Result;
+use sc_service::{config::MultiaddrWithPeerId, ChainType, Properties};
+use serde::Deserialize;
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_finality_grandpa::AuthorityId as GrandpaId;
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+/// Description of a network to generate a chain spec for.
+///
+/// Keys are given as SS58 public keys, never as seeds.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct NetworkDescription {
+	/// Human readable name of the chain.
+	pub name: String,
+	/// Chain id, also used as the base path directory name.
+	pub id: String,
+	/// The type of the chain.
+	#[serde(default = "default_chain_type")]
+	pub chain_type: ChainType,
+	/// Network protocol id.
+	pub protocol_id: Option<String>,
+	/// Bootnodes to connect to.
+	#[serde(default)]
+	pub boot_nodes: Vec<MultiaddrWithPeerId>,
+	/// Token properties shown by wallets, the runtime's own when omitted.
+	#[serde(default)]
+	pub properties: Option<TokenProperties>,
+	/// Initial Aura and GRANDPA authorities.
+	pub authorities: Vec<AuthorityKeys>,
+	/// Endowed accounts and their initial balances.
+	#[serde(default)]
+	pub balances: Vec<Endowment>,
+	/// Vesting schedules of endowed accounts.
+	#[serde(default)]
+	pub vesting: Vec<VestingSchedule>,
+	/// The sudo key.
+	pub sudo: AccountId,
+	/// Proof-of-existence claim limits.
+	#[serde(default)]
+	pub poe: Option<PoeLimits>,
+}
+
+/// Account and session keys of one genesis authority.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorityKeys {
+	/// The validator's account.
+	pub account: AccountId,
+	/// Aura (block authoring) key.
+	pub aura: AuraId,
+	/// GRANDPA (finality) key.
+	pub grandpa: GrandpaId,
+}
+
+/// An endowed account.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endowment {
+	/// The account to endow.
+	pub account: AccountId,
+	/// Its initial free balance.
+	pub amount: Amount,
+}
+
+/// A vesting schedule of an endowed account.
+///
+/// All but `liquid` of the account's balance is locked, and unlocks linearly over `length`
+/// blocks from block `begin`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VestingSchedule {
+	/// The vesting account.
+	pub account: AccountId,
+	/// The block vesting starts at.
+	#[serde(default)]
+	pub begin: BlockNumber,
+	/// The number of blocks until everything is unlocked.
+	pub length: BlockNumber,
+	/// The part of the balance that is not locked.
+	pub liquid: Amount,
+}
+
+/// A balance, given either as a number or as a decimal string for values TOML cannot represent.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Amount {
+	/// A number that fits into 64 bits.
+	Number(u64),
+	/// A decimal string.
+	Text(String),
+}
+
+impl Amount {
+	fn to_balance(&self) -> std::result::Result<Balance, String> {
+		match self {
+			Amount::Number(n) => Ok(*n as Balance),
+			Amount::Text(s) => s.parse().map_err(|e| format!("Invalid balance {:?}: {}", s, e)),
+		}
+	}
+}
+
+/// Token properties of the chain.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenProperties {
+	/// Token symbol, e.g. `POE`.
+	pub token_symbol: String,
+	/// Number of decimals of the token.
+	pub token_decimals: u8,
+	/// SS58 address format, which must be the runtime's `SS58_PREFIX`.
+	pub ss58_format: u16,
+}
+
+impl From<TokenProperties> for Properties {
+	fn from(p: TokenProperties) -> Self {
+		let mut properties = Properties::new();
+		properties.insert("tokenSymbol".into(), p.token_symbol.into());
+		properties.insert("tokenDecimals".into(), p.token_decimals.into());
+		properties.insert("ss58Format".into(), p.ss58_format.into());
+		properties
+	}
+}
+
+/// Claim length limits of the proof-of-existence pallet.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoeLimits {
+	/// The minimum length a claim may be.
+	pub min_length: u32,
+	/// The maximum length a claim may be.
+	pub max_length: u32,
+}
+
+fn default_chain_type() -> ChainType {
+	ChainType::Live
+}
+
+impl NetworkDescription {
+	/// Read a description from a `.toml` or `.json` file.
+	pub fn from_file(path: &Path) -> std::result::Result<Self, String> {
+		let raw = fs::read_to_string(path)
+			.map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+		match path.extension().and_then(|e| e.to_str()) {
+			Some("toml") => toml::from_str(&raw).map_err(|e| e.to_string()),
+			Some("json") => serde_json::from_str(&raw).map_err(|e| e.to_string()),
+			_ => Err(format!("{}: expected a .toml or .json file", path.display())),
+		}
+	}
+
+	/// Build the chain spec described.
+	pub fn to_chain_spec(&self) -> std::result::Result<ChainSpec, String> {
+		let wasm_binary = WASM_BINARY.ok_or_else(|| "Wasm binary not available".to_string())?;
+
+		if self.authorities.is_empty() {
+			return Err("At least one authority is required".into())
+		}
+		match &self.properties {
+			Some(properties) if properties.ss58_format != u16::from(SS58_PREFIX) =>
+				return Err(format!(
+					"ss58_format {} differs from the runtime's SS58 prefix {}",
+					properties.ss58_format, SS58_PREFIX
+				)),
+			_ => {},
+		}
+		let balances = self
+			.balances
+			.iter()
+			.map(|e| Ok((e.account.clone(), e.amount.to_balance()?)))
+			.collect::<std::result::Result<Vec<_>, String>>()?;
+		let vesting = self
+			.vesting
+			.iter()
+			.map(|v| {
+				let endowed = balances.iter().find(|(account, _)| account == &v.account);
+				let liquid = v.liquid.to_balance()?;
+				match endowed {
+					None => Err(format!("Vesting account {} is not endowed", v.account)),
+					Some((_, amount)) if liquid > *amount => Err(format!(
+						"Vesting account {} has less than its liquid balance",
+						v.account
+					)),
+					Some(_) if v.length == 0 =>
+						Err(format!("Vesting of {} must last at least one block", v.account)),
+					Some(_) => Ok((v.account.clone(), v.begin, v.length, liquid)),
+				}
+			})
+			.collect::<std::result::Result<Vec<_>, String>>()?;
+		let poe_length_limits = match &self.poe {
+			Some(PoeLimits { min_length, max_length }) if min_length > max_length =>
+				return Err("PoE min_length must not exceed max_length".into()),
+			Some(PoeLimits { min_length, max_length }) => Some((*min_length, *max_length)),
+			None => None,
+		};
+		let authorities: Vec<_> = self
+			.authorities
+			.iter()
+			.map(|a| (a.account.clone(), a.aura.clone(), a.grandpa.clone()))
+			.collect();
+		let sudo = self.sudo.clone();
+
+		Ok(ChainSpec::from_genesis(
+			&self.name,
+			&self.id,
+			self.chain_type.clone(),
+			move || {
+				chain_spec::testnet_genesis(
+					wasm_binary,
+					authorities.clone(),
+					sudo.clone(),
+					balances.clone(),
+					vesting.clone(),
+					poe_length_limits,
+					false,
+				)
+			},
+			self.boot_nodes.clone(),
+			None,
+			self.protocol_id.as_deref(),
+			Some(self.properties.clone().map_or_else(chain_spec::chain_properties, Into::into)),
+			None,
+		))
+	}
+}
+
+/// The `generate-spec` command used to build a raw chain spec from a network description.
+#[derive(Debug, StructOpt)]
+pub struct GenerateSpecCmd {
+	/// Path to the network description, a `.toml` or `.json` file.
+	#[structopt(parse(from_os_str))]
+	pub description: PathBuf,
+
+	/// Write the chain spec to this file instead of stdout.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
+impl GenerateSpecCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let spec = NetworkDescription::from_file(&self.description)?.to_chain_spec()?;
+		let json = sc_service::chain_ops::build_spec(&spec, true)?;
+
+		match &self.output {
+			Some(path) => fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+
+		Ok(())
+	}
+}
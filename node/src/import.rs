@@ -0,0 +1,440 @@
+//! The `poe import` command, importing claims from a legacy registry.
+//!
+//! Claims are read from a CSV file with `hash,owner,original_timestamp` rows, or from a JSON
+//! array of objects with these fields. Hashes are hex encoded, owners are SS58 addresses and
+//! timestamps are milliseconds since the Unix epoch. Every hash must be within the chain's
+//! claim length limits, or the batch holding it would fail.
+//!
+//! Claims are imported in batches of `force_import_claims` calls wrapped in `sudo`, so the
+//! import has to be completed before governance drops the sudo key. Each batch is valid for
+//! `DEFAULT_MORTALITY` blocks, like the transactions of `poe build`. The number of rows known
+//! to be on chain is kept in a checkpoint file, so an interrupted import resumes where it
+//! stopped. Once all rows are imported, every claim is looked up on chain.
+
+use crate::poe::{submit, ChainInfo, RpcParams, DEFAULT_MORTALITY};
+use codec::Decode;
+use futures_timer::Delay;
+use jsonrpc_core_client::{RpcError, TypedClient};
+use node_template_runtime::{
+	pallet_template, AccountId, BlockNumber, Call, Hash, MaxPoeImportBatch, MaxPoeLength,
+	MinPoeLength, Runtime, TemplateModule,
+};
+use sc_cli::Result;
+use serde::Deserialize;
+use sp_core::{
+	hashing::twox_128,
+	sr25519,
+	storage::{StorageChangeSet, StorageData, StorageKey},
+	Pair,
+};
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	str::FromStr,
+	time::Duration,
+};
+use structopt::StructOpt;
+
+/// How often to check whether a submitted batch made it on chain.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait for a submitted batch before giving up.
+const BATCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How many claims to look up at once when verifying the import.
+const VERIFY_CHUNK: usize = 1_000;
+
+/// The `poe import` command.
+#[derive(Debug, StructOpt)]
+pub struct ImportCmd {
+	/// The claims to import, as CSV or, if the file name ends in `.json`, JSON.
+	#[structopt(parse(from_os_str))]
+	pub file: PathBuf,
+
+	/// Secret URI of the sudo key.
+	#[structopt(long, value_name = "SURI")]
+	pub suri: String,
+
+	/// Where to record progress. Defaults to the claims file name with `.checkpoint` appended.
+	#[structopt(long, parse(from_os_str), value_name = "PATH")]
+	pub checkpoint: Option<PathBuf>,
+
+	/// Number of claims per extrinsic, at most the runtime's `MaxPoeImportBatch`.
+	#[structopt(long, value_name = "COUNT")]
+	pub batch_size: Option<usize>,
+
+	/// Only check that the claims are on chain, without importing any.
+	#[structopt(long)]
+	pub verify_only: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub rpc: RpcParams,
+}
+
+/// A claim from the legacy registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyClaim {
+	/// The claimed hash.
+	pub hash: Vec<u8>,
+	/// The owner of the claim.
+	pub owner: AccountId,
+	/// When the claim was made, in milliseconds since the Unix epoch.
+	pub original_timestamp: u64,
+}
+
+/// A claim as found in a JSON claims file.
+#[derive(Deserialize)]
+struct JsonClaim {
+	hash: String,
+	owner: String,
+	original_timestamp: u64,
+}
+
+impl LegacyClaim {
+	fn parse(
+		hash: &str,
+		owner: &str,
+		original_timestamp: u64,
+		(min, max): (u32, u32),
+	) -> std::result::Result<Self, String> {
+		let hash = sp_core::bytes::from_hex(hash.trim())
+			.map_err(|e| format!("Invalid hash {:?}: {:?}", hash, e))?;
+		if hash.len() < min as usize || hash.len() > max as usize {
+			return Err(format!("Hash of {} bytes, expected {} to {}", hash.len(), min, max))
+		}
+		let owner = AccountId::from_str(owner.trim())
+			.map_err(|e| format!("Invalid owner {:?}: {}", owner, e))?;
+		Ok(LegacyClaim { hash, owner, original_timestamp })
+	}
+}
+
+/// Read the claims of a CSV file, skipping blank lines and an optional header. Hashes must be
+/// within the claim length `limits`.
+pub fn parse_csv(
+	contents: &str,
+	limits: (u32, u32),
+) -> std::result::Result<Vec<LegacyClaim>, String> {
+	contents
+		.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.filter(|(n, line)| !(*n == 0 && line.trim_start().starts_with("hash")))
+		.map(|(n, line)| {
+			let fields = line.split(',').collect::<Vec<_>>();
+			match fields[..] {
+				[hash, owner, timestamp] => timestamp
+					.trim()
+					.parse()
+					.map_err(|e| format!("Invalid timestamp {:?}: {}", timestamp, e))
+					.and_then(|timestamp| LegacyClaim::parse(hash, owner, timestamp, limits)),
+				_ => Err(format!("Expected 3 fields, found {}", fields.len())),
+			}
+			.map_err(|e| format!("Line {}: {}", n + 1, e))
+		})
+		.collect()
+}
+
+/// Read the claims of a JSON file. Hashes must be within the claim length `limits`.
+pub fn parse_json(
+	contents: &str,
+	limits: (u32, u32),
+) -> std::result::Result<Vec<LegacyClaim>, String> {
+	let claims: Vec<JsonClaim> =
+		serde_json::from_str(contents).map_err(|e| format!("Invalid claims JSON: {}", e))?;
+	claims
+		.iter()
+		.enumerate()
+		.map(|(n, claim)| {
+			LegacyClaim::parse(&claim.hash, &claim.owner, claim.original_timestamp, limits)
+				.map_err(|e| format!("Claim {}: {}", n, e))
+		})
+		.collect()
+}
+
+impl ImportCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let max_batch = MaxPoeImportBatch::get() as usize;
+		let batch_size = self.batch_size.unwrap_or(max_batch);
+		if batch_size == 0 || batch_size > max_batch {
+			return Err(format!("The batch size must be between 1 and {}", max_batch).into())
+		}
+
+		let contents = fs::read_to_string(&self.file)
+			.map_err(|e| format!("Could not read {}: {}", self.file.display(), e))?;
+		let limits = self.rpc.with_client(|client| async move { length_limits(&client).await })?;
+		let claims = if self.file.extension().map_or(false, |e| e == "json") {
+			parse_json(&contents, limits)?
+		} else {
+			parse_csv(&contents, limits)?
+		};
+
+		let pair = sr25519::Pair::from_string(&self.suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+		let checkpoint = self.checkpoint.clone().unwrap_or_else(|| {
+			let mut name = self.file.clone().into_os_string();
+			name.push(".checkpoint");
+			name.into()
+		});
+		let start = if self.verify_only { claims.len() } else { read_checkpoint(&checkpoint)? };
+
+		self.rpc.with_client(|client| async move {
+			import(&client, &pair, &claims, start, batch_size, &checkpoint).await?;
+			verify(&client, &claims).await
+		})?
+	}
+}
+
+/// Import `claims` from `start` on, recording progress in `checkpoint`.
+async fn import(
+	client: &TypedClient,
+	pair: &sr25519::Pair,
+	claims: &[LegacyClaim],
+	start: usize,
+	batch_size: usize,
+	checkpoint: &Path,
+) -> std::result::Result<(), RpcError> {
+	if start >= claims.len() {
+		return Ok(())
+	}
+	println!("Importing {} claims from #{} on.", claims.len() - start, start);
+
+	let sudo = AccountId::from(pair.public());
+	match sudo_key(client).await? {
+		Some(key) if key == sudo => {},
+		Some(key) =>
+			return Err(RpcError::Client(format!("The sudo key is {}, not {}", key, sudo))),
+		None =>
+			return Err(RpcError::Client(
+				"The sudo key has been dropped; claims can no longer be imported".into(),
+			)),
+	}
+
+	let mut done = start;
+	for batch in claims[start..].chunks(batch_size) {
+		// Each batch is only sent once the previous one is on chain, so it gets a fresh nonce
+		// and an era starting at the current best block.
+		let info = ChainInfo::fetch(client, &sudo, Some(DEFAULT_MORTALITY)).await?;
+		let call: Call = pallet_template::Call::<Runtime>::force_import_claims(
+			batch
+				.iter()
+				.map(|c| (c.hash.clone(), c.owner.clone(), c.original_timestamp))
+				.collect(),
+		)
+		.into();
+		let sudo_call: Call = pallet_sudo::Call::<Runtime>::sudo(Box::new(call)).into();
+		submit(client, &crate::poe::sign(sudo_call, pair, &info)).await?;
+
+		// The batch is on chain once its claims are. Claims that existed before are skipped
+		// by the runtime but are present all the same.
+		let mut waited = Duration::from_secs(0);
+		while !missing(client, batch).await?.is_empty() {
+			if waited >= BATCH_TIMEOUT {
+				return Err(RpcError::Client(format!(
+					"Claims #{} to #{} did not make it on chain; check the sudo events and rerun \
+					 to resume",
+					done,
+					done + batch.len() - 1
+				)))
+			}
+			Delay::new(POLL_INTERVAL).await;
+			waited += POLL_INTERVAL;
+		}
+
+		done += batch.len();
+		write_checkpoint(checkpoint, done).map_err(RpcError::Client)?;
+		println!("Imported {}/{} claims.", done, claims.len());
+	}
+	Ok(())
+}
+
+/// The current sudo key, unless governance dropped it.
+async fn sudo_key(client: &TypedClient) -> std::result::Result<Option<AccountId>, RpcError> {
+	let key = StorageKey([twox_128(b"Sudo"), twox_128(b"Key")].concat());
+	let data: Option<StorageData> =
+		client.call_method("state_getStorage", "Option<StorageData>", (key,)).await?;
+	Ok(data.and_then(|data| AccountId::decode(&mut &data.0[..]).ok()))
+}
+
+/// The claim length limits `(min, max)` of the chain, which `force_import_claims` checks.
+async fn length_limits(client: &TypedClient) -> std::result::Result<(u32, u32), RpcError> {
+	let key = StorageKey(TemplateModule::length_limits_key());
+	let data: Option<StorageData> =
+		client.call_method("state_getStorage", "Option<StorageData>", (key,)).await?;
+	Ok(data
+		.and_then(|data| <(u32, u32)>::decode(&mut &data.0[..]).ok())
+		.unwrap_or_else(|| (MinPoeLength::get(), MaxPoeLength::get())))
+}
+
+/// Check that all `claims` are on chain, reporting those owned by someone else.
+async fn verify(
+	client: &TypedClient,
+	claims: &[LegacyClaim],
+) -> std::result::Result<Result<()>, RpcError> {
+	let mut absent = 0;
+	let mut owned_by_others = 0;
+	for chunk in claims.chunks(VERIFY_CHUNK) {
+		for (claim, owner) in chunk.iter().zip(owners(client, chunk).await?) {
+			let hash = sp_core::hexdisplay::HexDisplay::from(&claim.hash);
+			match owner {
+				None => {
+					absent += 1;
+					eprintln!("Missing: 0x{}", hash);
+				},
+				Some(owner) if owner != claim.owner => {
+					owned_by_others += 1;
+					eprintln!("Claimed by {} instead of {}: 0x{}", owner, claim.owner, hash);
+				},
+				Some(_) => {},
+			}
+		}
+	}
+
+	println!(
+		"Verified {} claims: {} missing, {} claimed by another owner.",
+		claims.len(),
+		absent,
+		owned_by_others
+	);
+	Ok(if absent == 0 {
+		Ok(())
+	} else {
+		Err(format!("{} claims are missing on chain", absent).into())
+	})
+}
+
+/// The claims of `batch` that are not on chain.
+async fn missing<'a>(
+	client: &TypedClient,
+	batch: &'a [LegacyClaim],
+) -> std::result::Result<Vec<&'a LegacyClaim>, RpcError> {
+	let owners = owners(client, batch).await?;
+	Ok(batch
+		.iter()
+		.zip(owners)
+		.filter(|(_, owner)| owner.is_none())
+		.map(|(c, _)| c)
+		.collect())
+}
+
+/// The current owners of `claims`, at the best block.
+async fn owners(
+	client: &TypedClient,
+	claims: &[LegacyClaim],
+) -> std::result::Result<Vec<Option<AccountId>>, RpcError> {
+	let keys = claims
+		.iter()
+		.map(|c| StorageKey(TemplateModule::proof_key(&c.hash)))
+		.collect::<Vec<_>>();
+	let change_sets: Vec<StorageChangeSet<Hash>> = client
+		.call_method("state_queryStorageAt", "Vec<StorageChangeSet>", (keys.clone(),))
+		.await?;
+
+	let values = change_sets.into_iter().flat_map(|set| set.changes).collect::<Vec<_>>();
+	Ok(keys
+		.iter()
+		.map(|key| {
+			values
+				.iter()
+				.find(|(k, _)| k == key)
+				.and_then(|(_, data)| data.as_ref())
+				.and_then(|data| <(AccountId, BlockNumber)>::decode(&mut &data.0[..]).ok())
+				.map(|(owner, _)| owner)
+		})
+		.collect())
+}
+
+/// The number of claims already imported according to `checkpoint`.
+fn read_checkpoint(checkpoint: &Path) -> Result<usize> {
+	if !checkpoint.exists() {
+		return Ok(0)
+	}
+	let raw = fs::read_to_string(checkpoint)
+		.map_err(|e| format!("Could not read {}: {}", checkpoint.display(), e))?;
+	Ok(raw
+		.trim()
+		.parse()
+		.map_err(|e| format!("Invalid checkpoint {}: {}", checkpoint.display(), e))?)
+}
+
+/// Record that the first `done` claims are imported, replacing `checkpoint` atomically.
+fn write_checkpoint(checkpoint: &Path, done: usize) -> std::result::Result<(), String> {
+	let mut temporary = checkpoint.as_os_str().to_owned();
+	temporary.push(".tmp");
+	fs::write(&temporary, format!("{}\n", done))
+		.and_then(|_| fs::rename(&temporary, checkpoint))
+		.map_err(|e| format!("Could not write {}: {}", checkpoint.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::Ss58Codec;
+
+	/// Claim length limits that the two-byte hashes of the tests are within.
+	const LIMITS: (u32, u32) = (2, 4);
+
+	fn alice() -> AccountId {
+		AccountId::from(sr25519::Pair::from_string("//Alice", None).unwrap().public())
+	}
+
+	#[test]
+	fn csv_and_json_claims_are_parsed_alike() {
+		let owner = alice().to_ss58check();
+		let csv = format!("hash,owner,original_timestamp\n0x0102,{0},5\n\n0304, {0} ,6\n", owner);
+		let json = format!(
+			r#"[{{"hash":"0x0102","owner":"{0}","original_timestamp":5}},
+			{{"hash":"0304","owner":"{0}","original_timestamp":6}}]"#,
+			owner
+		);
+		let expected = vec![
+			LegacyClaim { hash: vec![1, 2], owner: alice(), original_timestamp: 5 },
+			LegacyClaim { hash: vec![3, 4], owner: alice(), original_timestamp: 6 },
+		];
+
+		assert_eq!(parse_csv(&csv, LIMITS).unwrap(), expected);
+		assert_eq!(parse_json(&json, LIMITS).unwrap(), expected);
+	}
+
+	#[test]
+	fn invalid_rows_are_reported_with_their_line() {
+		let owner = alice().to_ss58check();
+
+		let csv = format!("0x0102,{},5\n0x0304,{}\n", owner, owner);
+		let error = parse_csv(&csv, LIMITS).unwrap_err();
+		assert!(error.starts_with("Line 2: Expected 3 fields"), "{}", error);
+		let error = parse_csv(&format!("0x0102,{},soon\n", owner), LIMITS).unwrap_err();
+		assert!(error.starts_with("Line 1: Invalid timestamp"), "{}", error);
+		let error = parse_csv("0x0102,nobody,5\n", LIMITS).unwrap_err();
+		assert!(error.starts_with("Line 1: Invalid owner"), "{}", error);
+	}
+
+	#[test]
+	fn hashes_beyond_the_length_limits_are_reported_with_their_line() {
+		let owner = alice().to_ss58check();
+
+		let csv =
+			format!("hash,owner,original_timestamp\n0x0102,{0},5\n0x0102030405,{0},6\n", owner);
+		let error = parse_csv(&csv, LIMITS).unwrap_err();
+		assert_eq!(error, "Line 3: Hash of 5 bytes, expected 2 to 4");
+		let error = parse_csv(&format!("0x01,{},5\n", owner), LIMITS).unwrap_err();
+		assert_eq!(error, "Line 1: Hash of 1 bytes, expected 2 to 4");
+
+		let json = format!(r#"[{{"hash":"0x01","owner":"{}","original_timestamp":5}}]"#, owner);
+		let error = parse_json(&json, LIMITS).unwrap_err();
+		assert_eq!(error, "Claim 0: Hash of 1 bytes, expected 2 to 4");
+	}
+
+	#[test]
+	fn checkpoints_survive_restarts() {
+		let dir = tempfile::tempdir().unwrap();
+		let checkpoint = dir.path().join("claims.csv.checkpoint");
+
+		assert_eq!(read_checkpoint(&checkpoint).unwrap(), 0);
+		write_checkpoint(&checkpoint, 2_000).unwrap();
+		assert_eq!(read_checkpoint(&checkpoint).unwrap(), 2_000);
+		write_checkpoint(&checkpoint, 3_000).unwrap();
+		assert_eq!(read_checkpoint(&checkpoint).unwrap(), 3_000);
+	}
+}
@@ -0,0 +1,628 @@
+//! An SQLite index of the proof-of-existence events of the best chain, enabled with
+//! `--poe-index-db`.
+//!
+//! Every block of the best chain is recorded with its timestamp and claim events. When the best
+//! chain changes, the blocks that left it are rolled back before the new ones are written; such
+//! blocks are never finalized, since finalized blocks cannot be reverted. The index is queried
+//! through the `poe_query*` RPCs.
+//!
+//! Indexing starts from genesis, so the node must keep the state of every block: the service
+//! refuses to open the index without `--pruning archive`.
+
+use codec::Decode;
+use futures::{stream, StreamExt};
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash};
+use rusqlite::{
+	params, params_from_iter,
+	types::{Type, Value},
+	Connection, OptionalExtension, Transaction,
+};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::AccountId32, storage::StorageKey, twox_128};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use std::{
+	convert::TryInto,
+	fmt,
+	path::Path,
+	sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+	events::block_events,
+	rpc::{ClaimEvent, ClaimEventKind},
+};
+
+/// The number of blocks written per database transaction while catching up.
+const INDEX_BATCH: usize = 512;
+
+/// The number of events returned by a query when no limit is given.
+const DEFAULT_LIMIT: u32 = 100;
+
+/// The most events returned by a single query.
+const MAX_LIMIT: u32 = 1_000;
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS blocks (
+		number INTEGER PRIMARY KEY,
+		hash BLOB NOT NULL,
+		timestamp INTEGER NOT NULL,
+		finalized INTEGER NOT NULL
+	);
+	CREATE TABLE IF NOT EXISTS events (
+		block_number INTEGER NOT NULL REFERENCES blocks (number) ON DELETE CASCADE,
+		event_index INTEGER NOT NULL,
+		kind TEXT NOT NULL,
+		claim BLOB NOT NULL,
+		owner BLOB NOT NULL,
+		dest BLOB,
+		original_timestamp INTEGER,
+		PRIMARY KEY (block_number, event_index)
+	);
+	CREATE INDEX IF NOT EXISTS events_by_owner ON events (owner, block_number);
+	CREATE INDEX IF NOT EXISTS events_by_dest ON events (dest, block_number);
+	CREATE INDEX IF NOT EXISTS blocks_by_timestamp ON blocks (timestamp);
+";
+
+/// An error while indexing blocks.
+#[derive(Debug)]
+pub enum Error {
+	/// A block or its state could not be read.
+	Client(sp_blockchain::Error),
+	/// The index could not be read or written.
+	Database(rusqlite::Error),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Client(e) => write!(f, "{}", e),
+			Error::Database(e) => write!(f, "Index database error: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<sp_blockchain::Error> for Error {
+	fn from(e: sp_blockchain::Error) -> Self {
+		Error::Client(e)
+	}
+}
+
+impl From<rusqlite::Error> for Error {
+	fn from(e: rusqlite::Error) -> Self {
+		Error::Database(e)
+	}
+}
+
+/// Where a page of events starts: right after the given event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventCursor {
+	/// The block of the event.
+	pub block_number: BlockNumber,
+	/// The position of the event among the events of its block.
+	pub event_index: u32,
+}
+
+/// Which indexed events to return, oldest first. Every criterion left out matches anything.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventQuery {
+	/// Only events of claims owned by, or transferred to, this account.
+	pub owner: Option<AccountId>,
+	/// Only events of these kinds.
+	pub kinds: Option<Vec<ClaimEventKind>>,
+	/// Only events of blocks made at or after this time, in milliseconds since the Unix epoch.
+	pub from: Option<u64>,
+	/// Only events of blocks made before this time, in milliseconds since the Unix epoch.
+	pub to: Option<u64>,
+	/// Only events of finalized blocks.
+	#[serde(default)]
+	pub finalized_only: bool,
+	/// Only events after this one, as returned in `next` by the previous page.
+	pub after: Option<EventCursor>,
+	/// How many events to return, 100 by default and at most 1000.
+	pub limit: Option<u32>,
+}
+
+/// An event of the index.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedEvent {
+	/// The event itself.
+	#[serde(flatten)]
+	pub event: ClaimEvent,
+	/// The position of the event among the events of its block.
+	pub event_index: u32,
+	/// When the block of the event was made, in milliseconds since the Unix epoch.
+	pub block_timestamp: u64,
+	/// Whether the block of the event is finalized.
+	pub finalized: bool,
+}
+
+/// A page of events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventPage {
+	/// The events of the page, oldest first.
+	pub events: Vec<IndexedEvent>,
+	/// Where the next page starts, if there may be one.
+	pub next: Option<EventCursor>,
+}
+
+/// How far the index goes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStatus {
+	/// The last indexed block, the best block once the index has caught up.
+	pub best: Option<BlockNumber>,
+	/// The last finalized indexed block.
+	pub finalized: Option<BlockNumber>,
+	/// The number of indexed events.
+	pub events: u64,
+}
+
+/// A block of the best chain and its claim events.
+#[derive(Debug, Clone, PartialEq)]
+struct IndexedBlock {
+	hash: Hash,
+	number: BlockNumber,
+	timestamp: u64,
+	finalized: bool,
+	/// The claim events of the block, along with their position among all its events.
+	events: Vec<(u32, ClaimEvent)>,
+}
+
+impl IndexedBlock {
+	/// Read block `hash` and its claim events from `client`.
+	fn read<C, BA>(
+		client: &C,
+		hash: Hash,
+		number: BlockNumber,
+		finalized: bool,
+	) -> Result<Self, Error>
+	where
+		C: StorageProvider<Block, BA>,
+		BA: Backend<Block>,
+	{
+		let id = BlockId::Hash(hash);
+		let now = StorageKey([twox_128(b"Timestamp"), twox_128(b"Now")].concat());
+		let timestamp = client
+			.storage(&id, &now)?
+			.and_then(|value| u64::decode(&mut &value.0[..]).ok())
+			.unwrap_or_default();
+		let events = block_events(client, hash)?
+			.into_iter()
+			.enumerate()
+			.filter_map(|(index, event)| {
+				ClaimEvent::from_event(hash, number, event).map(|event| (index as u32, event))
+			})
+			.collect();
+		Ok(IndexedBlock { hash, number, timestamp, finalized, events })
+	}
+}
+
+fn kind_name(kind: ClaimEventKind) -> &'static str {
+	match kind {
+		ClaimEventKind::Created => "created",
+		ClaimEventKind::Revoked => "revoked",
+		ClaimEventKind::Transferred => "transferred",
+		ClaimEventKind::Imported => "imported",
+	}
+}
+
+fn conversion_error(column: usize, kind: Type, message: String) -> rusqlite::Error {
+	rusqlite::Error::FromSqlConversionFailure(column, kind, message.into())
+}
+
+fn kind_column(row: &rusqlite::Row, column: usize) -> rusqlite::Result<ClaimEventKind> {
+	let name: String = row.get(column)?;
+	match name.as_str() {
+		"created" => Ok(ClaimEventKind::Created),
+		"revoked" => Ok(ClaimEventKind::Revoked),
+		"transferred" => Ok(ClaimEventKind::Transferred),
+		"imported" => Ok(ClaimEventKind::Imported),
+		_ => Err(conversion_error(column, Type::Text, format!("Unknown event kind {:?}", name))),
+	}
+}
+
+fn bytes32_column(row: &rusqlite::Row, column: usize) -> rusqlite::Result<[u8; 32]> {
+	let bytes: Vec<u8> = row.get(column)?;
+	let len = bytes.len();
+	bytes.try_into().map_err(|_| {
+		conversion_error(column, Type::Blob, format!("Expected 32 bytes, got {}", len))
+	})
+}
+
+/// The proof-of-existence index, shared by the indexing task and the RPCs.
+pub struct PoeIndex {
+	connection: Mutex<Connection>,
+}
+
+impl PoeIndex {
+	/// Open the index at `path`, creating it if needed.
+	pub fn open(path: &Path) -> rusqlite::Result<Self> {
+		Self::new(Connection::open(path)?)
+	}
+
+	fn new(connection: Connection) -> rusqlite::Result<Self> {
+		// WAL lets other processes read the index while the node writes to it. The RPCs share
+		// this connection, so they wait for the batch of blocks being written.
+		connection.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+		// Roll back events with their blocks.
+		connection.pragma_update(None, "foreign_keys", &true)?;
+		connection.execute_batch(SCHEMA)?;
+		Ok(PoeIndex { connection: Mutex::new(connection) })
+	}
+
+	fn connection(&self) -> MutexGuard<Connection> {
+		self.connection.lock().expect("The index is only locked by code that does not panic; qed")
+	}
+
+	/// The hash of the indexed block `number`, if there is one.
+	fn indexed_hash(&self, number: BlockNumber) -> rusqlite::Result<Option<Hash>> {
+		self.connection()
+			.query_row("SELECT hash FROM blocks WHERE number = ?", [number], |row| {
+				bytes32_column(row, 0).map(Hash::from)
+			})
+			.optional()
+	}
+
+	/// Write `blocks` after removing every indexed block from `roll_back_from` on.
+	fn write(
+		&self,
+		roll_back_from: Option<BlockNumber>,
+		blocks: &[IndexedBlock],
+	) -> rusqlite::Result<()> {
+		let mut connection = self.connection();
+		let transaction = connection.transaction()?;
+		if let Some(number) = roll_back_from {
+			transaction.execute("DELETE FROM blocks WHERE number >= ?", [number])?;
+		}
+		for block in blocks {
+			Self::insert(&transaction, block)?;
+		}
+		transaction.commit()
+	}
+
+	fn insert(transaction: &Transaction, block: &IndexedBlock) -> rusqlite::Result<()> {
+		transaction.execute(
+			"INSERT INTO blocks (number, hash, timestamp, finalized) VALUES (?, ?, ?, ?)",
+			params![block.number, block.hash.as_bytes(), block.timestamp as i64, block.finalized],
+		)?;
+		let mut insert_event = transaction.prepare_cached(
+			"INSERT INTO events
+				(block_number, event_index, kind, claim, owner, dest, original_timestamp)
+			VALUES (?, ?, ?, ?, ?, ?, ?)",
+		)?;
+		for (index, event) in &block.events {
+			insert_event.execute(params![
+				block.number,
+				index,
+				kind_name(event.kind),
+				&event.claim.0,
+				AsRef::<[u8]>::as_ref(&event.owner),
+				event.dest.as_ref().map(AsRef::<[u8]>::as_ref),
+				event.timestamp.map(|timestamp| timestamp as i64),
+			])?;
+		}
+		Ok(())
+	}
+
+	/// Mark every indexed block up to `number` as finalized.
+	fn finalize(&self, number: BlockNumber) -> rusqlite::Result<()> {
+		self.connection()
+			.execute(
+				"UPDATE blocks SET finalized = 1 WHERE number <= ? AND finalized = 0",
+				[number],
+			)
+			.map(drop)
+	}
+
+	/// Bring the index in line with the chain ending at block `best`.
+	pub fn sync<C, BA>(&self, client: &C, best: Hash) -> Result<(), Error>
+	where
+		C: StorageProvider<Block, BA> + HeaderBackend<Block>,
+		BA: Backend<Block>,
+	{
+		// Walk back from `best` to the last block the index agrees on.
+		let mut missing = Vec::new();
+		let mut hash = best;
+		loop {
+			let header = client
+				.header(BlockId::Hash(hash))?
+				.ok_or_else(|| sp_blockchain::Error::UnknownBlock(hash.to_string()))?;
+			let number = *header.number();
+			if self.indexed_hash(number)? == Some(hash) {
+				break
+			}
+			missing.push((hash, number));
+			if number == 0 {
+				break
+			}
+			hash = *header.parent_hash();
+		}
+		let mut roll_back_from = match missing.last() {
+			Some((_, number)) => Some(*number),
+			None => return Ok(()),
+		};
+
+		let finalized_number = client.info().finalized_number;
+		missing.reverse();
+		for chunk in missing.chunks(INDEX_BATCH) {
+			let blocks = chunk
+				.iter()
+				.map(|(hash, number)| {
+					IndexedBlock::read(client, *hash, *number, *number <= finalized_number)
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			self.write(roll_back_from.take(), &blocks)?;
+		}
+		Ok(())
+	}
+
+	/// The events matching `query`.
+	pub fn query(&self, query: &EventQuery) -> rusqlite::Result<EventPage> {
+		let mut conditions = Vec::new();
+		let mut values = Vec::new();
+		if let Some(owner) = &query.owner {
+			conditions.push("(e.owner = ? OR e.dest = ?)".to_string());
+			let owner: &[u8] = owner.as_ref();
+			values.push(Value::Blob(owner.to_vec()));
+			values.push(Value::Blob(owner.to_vec()));
+		}
+		if let Some(kinds) = &query.kinds {
+			conditions.push(format!("e.kind IN ({})", vec!["?"; kinds.len()].join(", ")));
+			values.extend(kinds.iter().map(|kind| Value::Text(kind_name(*kind).into())));
+		}
+		if let Some(from) = query.from {
+			conditions.push("b.timestamp >= ?".into());
+			values.push(Value::Integer(from as i64));
+		}
+		if let Some(to) = query.to {
+			conditions.push("b.timestamp < ?".into());
+			values.push(Value::Integer(to as i64));
+		}
+		if query.finalized_only {
+			conditions.push("b.finalized = 1".into());
+		}
+		if let Some(after) = query.after {
+			conditions.push("(e.block_number, e.event_index) > (?, ?)".into());
+			values.push(Value::Integer(after.block_number.into()));
+			values.push(Value::Integer(after.event_index.into()));
+		}
+		let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+		values.push(Value::Integer(limit.into()));
+
+		let sql = format!(
+			"SELECT e.block_number, e.event_index, b.hash, b.timestamp, b.finalized,
+				e.kind, e.claim, e.owner, e.dest, e.original_timestamp
+			FROM events e JOIN blocks b ON b.number = e.block_number
+			{} {}
+			ORDER BY e.block_number, e.event_index
+			LIMIT ?",
+			if conditions.is_empty() { "" } else { "WHERE" },
+			conditions.join(" AND "),
+		);
+		let connection = self.connection();
+		let mut statement = connection.prepare(&sql)?;
+		let events = statement
+			.query_map(params_from_iter(values), |row| {
+				let dest: Option<Vec<u8>> = row.get(8)?;
+				Ok(IndexedEvent {
+					event: ClaimEvent {
+						block_hash: bytes32_column(row, 2)?.into(),
+						block_number: row.get(0)?,
+						kind: kind_column(row, 5)?,
+						claim: row.get::<_, Vec<u8>>(6)?.into(),
+						owner: AccountId32::new(bytes32_column(row, 7)?),
+						dest: match dest {
+							Some(_) => Some(AccountId32::new(bytes32_column(row, 8)?)),
+							None => None,
+						},
+						timestamp: row.get::<_, Option<i64>>(9)?.map(|timestamp| timestamp as u64),
+					},
+					event_index: row.get(1)?,
+					block_timestamp: row.get::<_, i64>(3)? as u64,
+					finalized: row.get(4)?,
+				})
+			})?
+			.collect::<rusqlite::Result<Vec<_>>>()?;
+
+		let next = match events.last() {
+			Some(last) if events.len() == limit as usize => Some(EventCursor {
+				block_number: last.event.block_number,
+				event_index: last.event_index,
+			}),
+			_ => None,
+		};
+		Ok(EventPage { events, next })
+	}
+
+	/// How far the index goes.
+	pub fn status(&self) -> rusqlite::Result<IndexStatus> {
+		let connection = self.connection();
+		let (best, finalized) = connection.query_row(
+			"SELECT MAX(number), MAX(CASE WHEN finalized THEN number END) FROM blocks",
+			[],
+			|row| Ok((row.get(0)?, row.get(1)?)),
+		)?;
+		let events: i64 =
+			connection.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))?;
+		Ok(IndexStatus { best, finalized, events: events as u64 })
+	}
+}
+
+/// A change of the chain the index follows.
+enum ChainEvent {
+	Best(Hash),
+	Finalized(BlockNumber),
+}
+
+/// Keep `index` in line with the best chain of `client`.
+pub async fn run<C, BA>(client: Arc<C>, index: Arc<PoeIndex>)
+where
+	C: BlockchainEvents<Block> + StorageProvider<Block, BA> + HeaderBackend<Block>,
+	BA: Backend<Block>,
+{
+	// Subscribe before catching up, so that no block is missed in between.
+	let best = client
+		.import_notification_stream()
+		.filter(|notification| futures::future::ready(notification.is_new_best))
+		.map(|notification| ChainEvent::Best(notification.hash));
+	let finalized = client
+		.finality_notification_stream()
+		.map(|notification| ChainEvent::Finalized(*notification.header.number()));
+	let mut events = stream::select(best, finalized);
+
+	let info = client.info();
+	if let Err(e) = index.sync(&*client, info.best_hash) {
+		log::warn!("Indexing claims up to {} failed: {}", info.best_hash, e);
+	}
+
+	while let Some(event) = events.next().await {
+		let result = match event {
+			ChainEvent::Best(hash) => index.sync(&*client, hash),
+			ChainEvent::Finalized(number) => index.finalize(number).map_err(Into::into),
+		};
+		if let Err(e) = result {
+			log::warn!("Indexing claims failed: {}", e);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn account(seed: u8) -> AccountId {
+		AccountId32::new([seed; 32])
+	}
+
+	fn event(number: BlockNumber, kind: ClaimEventKind, owner: u8, claim: &[u8]) -> ClaimEvent {
+		ClaimEvent {
+			block_hash: Hash::repeat_byte(number as u8),
+			block_number: number,
+			kind,
+			claim: claim.to_vec().into(),
+			owner: account(owner),
+			dest: None,
+			timestamp: None,
+		}
+	}
+
+	fn block(number: BlockNumber, events: Vec<ClaimEvent>) -> IndexedBlock {
+		IndexedBlock {
+			hash: Hash::repeat_byte(number as u8),
+			number,
+			timestamp: 1_000 * number as u64,
+			finalized: false,
+			events: events.into_iter().enumerate().map(|(i, e)| (i as u32 * 2, e)).collect(),
+		}
+	}
+
+	fn index() -> PoeIndex {
+		let index = PoeIndex::new(Connection::open_in_memory().unwrap()).unwrap();
+		let mut transferred = event(2, ClaimEventKind::Transferred, 1, b"first");
+		transferred.dest = Some(account(2));
+		let mut imported = event(3, ClaimEventKind::Imported, 3, b"third");
+		imported.timestamp = Some(42);
+		index
+			.write(
+				None,
+				&[
+					block(0, vec![]),
+					block(
+						1,
+						vec![
+							event(1, ClaimEventKind::Created, 1, b"first"),
+							event(1, ClaimEventKind::Created, 1, b"second"),
+						],
+					),
+					block(2, vec![transferred]),
+					block(3, vec![imported]),
+				],
+			)
+			.unwrap();
+		index
+	}
+
+	fn claims(page: &EventPage) -> Vec<&[u8]> {
+		page.events.iter().map(|e| &e.event.claim.0[..]).collect()
+	}
+
+	#[test]
+	fn events_round_trip() {
+		let page = index().query(&EventQuery::default()).unwrap();
+
+		assert_eq!(claims(&page), vec![&b"first"[..], b"second", b"first", b"third"]);
+		assert_eq!(page.events[1].event_index, 2);
+		assert_eq!(page.events[2].event.dest, Some(account(2)));
+		assert_eq!(page.events[3].event.timestamp, Some(42));
+		assert_eq!(page.events[3].block_timestamp, 3_000);
+		assert_eq!(page.next, None);
+	}
+
+	#[test]
+	fn queries_filter_events() {
+		let index = index();
+		let query = |query: EventQuery| index.query(&query).unwrap();
+
+		let by_owner = query(EventQuery { owner: Some(account(2)), ..Default::default() });
+		assert_eq!(claims(&by_owner), vec![&b"first"[..]]);
+
+		let by_kind = query(EventQuery {
+			kinds: Some(vec![ClaimEventKind::Transferred, ClaimEventKind::Imported]),
+			..Default::default()
+		});
+		assert_eq!(claims(&by_kind), vec![&b"first"[..], b"third"]);
+
+		let by_time =
+			query(EventQuery { from: Some(2_000), to: Some(3_000), ..Default::default() });
+		assert_eq!(claims(&by_time), vec![&b"first"[..]]);
+
+		assert!(query(EventQuery { finalized_only: true, ..Default::default() })
+			.events
+			.is_empty());
+		index.finalize(1).unwrap();
+		let finalized = query(EventQuery { finalized_only: true, ..Default::default() });
+		assert_eq!(claims(&finalized), vec![&b"first"[..], b"second"]);
+	}
+
+	#[test]
+	fn queries_are_paginated() {
+		let index = index();
+
+		let first = index.query(&EventQuery { limit: Some(3), ..Default::default() }).unwrap();
+		assert_eq!(claims(&first), vec![&b"first"[..], b"second", b"first"]);
+		assert_eq!(first.next, Some(EventCursor { block_number: 2, event_index: 0 }));
+
+		let second = index
+			.query(&EventQuery { limit: Some(3), after: first.next, ..Default::default() })
+			.unwrap();
+		assert_eq!(claims(&second), vec![&b"third"[..]]);
+		assert_eq!(second.next, None);
+	}
+
+	#[test]
+	fn roll_back_replaces_blocks() {
+		let index = index();
+		let mut fork = block(2, vec![event(2, ClaimEventKind::Revoked, 1, b"second")]);
+		fork.hash = Hash::repeat_byte(0xff);
+
+		index.write(Some(2), &[fork]).unwrap();
+
+		assert_eq!(index.indexed_hash(2).unwrap(), Some(Hash::repeat_byte(0xff)));
+		assert_eq!(index.indexed_hash(3).unwrap(), None);
+		let page = index.query(&EventQuery::default()).unwrap();
+		assert_eq!(claims(&page), vec![&b"first"[..], b"second", b"second"]);
+		assert_eq!(page.events[2].event.kind, ClaimEventKind::Revoked);
+		assert_eq!(
+			index.status().unwrap(),
+			IndexStatus { best: Some(2), finalized: None, events: 3 }
+		);
+	}
+}
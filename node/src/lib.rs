@@ -1,3 +1,13 @@
+pub mod certificate;
 pub mod chain_spec;
+pub mod events;
+pub mod export;
+pub mod cli;
+pub mod generate_spec;
+pub mod import;
+pub mod indexer;
+pub mod metrics;
+pub mod poe;
+pub mod remote_keystore;
 pub mod rpc;
 pub mod service;
@@ -1,11 +1,20 @@
 //! Substrate Node Template CLI library.
 #![warn(missing_docs)]
 
+mod certificate;
 mod chain_spec;
+mod events;
+mod export;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
+mod generate_spec;
+mod import;
+mod indexer;
+mod metrics;
+mod poe;
+mod remote_keystore;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
@@ -0,0 +1,355 @@
+//! Prometheus metrics of proof-of-existence activity.
+//!
+//! The registry is counted once from storage when the node starts, then kept up to date from the
+//! events of every new best block. On a re-organisation, the events of retracted blocks are undone
+//! and those of enacted blocks applied, so storage is only counted again when notifications of
+//! best blocks were missed, as happens during major sync.
+//!
+//! Claim events are counted rather than reported per block, so that no block is lost between two
+//! scrapes. Blocks enacted again after a re-organisation are counted again.
+
+use codec::Decode;
+use futures::StreamExt;
+use node_template_runtime::{
+	opaque::Block, pallet_template, AccountId, BlockNumber, Event, Hash, TemplateModule,
+};
+use sc_client_api::{Backend, BlockImportNotification, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+use std::{collections::HashMap, sync::Arc};
+use substrate_prometheus_endpoint::{
+	register, CounterVec, Gauge, Histogram, HistogramOpts, Opts, PrometheusError, Registry, U64,
+};
+
+use crate::events::block_events;
+
+/// The metrics of the proof-of-existence registry.
+#[derive(Clone)]
+pub struct PoeMetrics {
+	claims: Gauge<U64>,
+	owners: Gauge<U64>,
+	claim_events: CounterVec<U64>,
+	claim_size: Histogram,
+}
+
+impl PoeMetrics {
+	/// Create the metrics and register them on `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			claims: register(
+				Gauge::new("poe_claims", "Number of claims in the registry")?,
+				registry,
+			)?,
+			owners: register(
+				Gauge::new("poe_claim_owners", "Number of accounts owning at least one claim")?,
+				registry,
+			)?,
+			claim_events: register(
+				CounterVec::new(
+					Opts::new(
+						"poe_claim_events_total",
+						"Claims created, revoked, transferred or imported in best blocks",
+					),
+					&["event"],
+				)?,
+				registry,
+			)?,
+			claim_size: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"poe_claim_size_bytes",
+						"Length of created and imported claims",
+					)
+					.buckets(vec![8.0, 16.0, 32.0, 64.0, 128.0, 256.0]),
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Report the registry as of a new best block, along with the activity of the blocks enacted
+	/// by it.
+	fn report(&self, holdings: &Holdings, activity: &Activity) {
+		self.claims.set(holdings.claims());
+		self.owners.set(holdings.owners());
+		for (event, count) in &[
+			("created", activity.created),
+			("revoked", activity.revoked),
+			("transferred", activity.transferred),
+			("imported", activity.imported),
+		] {
+			self.claim_events.with_label_values(&[*event]).inc_by(*count);
+		}
+		for size in &activity.sizes {
+			self.claim_size.observe(*size as f64);
+		}
+	}
+}
+
+/// The number of claims held by every owner.
+#[derive(Debug, Default, PartialEq)]
+struct Holdings(HashMap<AccountId, u64>);
+
+impl Holdings {
+	fn claims(&self) -> u64 {
+		self.0.values().sum()
+	}
+
+	fn owners(&self) -> u64 {
+		self.0.len() as u64
+	}
+
+	fn add(&mut self, owner: AccountId) {
+		*self.0.entry(owner).or_default() += 1;
+	}
+
+	fn remove(&mut self, owner: &AccountId) {
+		if let Some(held) = self.0.get_mut(owner) {
+			*held -= 1;
+			if *held == 0 {
+				self.0.remove(owner);
+			}
+		}
+	}
+
+	/// Account for the events of a block, adding what happened to claims in it to `activity`.
+	fn apply(&mut self, events: impl IntoIterator<Item = Event>, activity: &mut Activity) {
+		use pallet_template::Event as PoeEvent;
+
+		for event in events {
+			match event {
+				Event::TemplateModule(PoeEvent::ClaimCreated(owner, claim)) => {
+					self.add(owner);
+					activity.created += 1;
+					activity.sizes.push(claim.len());
+				},
+				Event::TemplateModule(PoeEvent::ClaimImported(owner, claim, _)) => {
+					self.add(owner);
+					activity.imported += 1;
+					activity.sizes.push(claim.len());
+				},
+				Event::TemplateModule(PoeEvent::ClaimRevoked(owner, _)) => {
+					self.remove(&owner);
+					activity.revoked += 1;
+				},
+				Event::TemplateModule(PoeEvent::ClaimTransferred(from, to, _)) => {
+					self.remove(&from);
+					self.add(to);
+					activity.transferred += 1;
+				},
+				_ => {},
+			}
+		}
+	}
+
+	/// Undo the events of a retracted block.
+	fn revert(&mut self, events: Vec<Event>) {
+		use pallet_template::Event as PoeEvent;
+
+		for event in events.into_iter().rev() {
+			match event {
+				Event::TemplateModule(PoeEvent::ClaimCreated(owner, _)) |
+				Event::TemplateModule(PoeEvent::ClaimImported(owner, _, _)) => self.remove(&owner),
+				Event::TemplateModule(PoeEvent::ClaimRevoked(owner, _)) => self.add(owner),
+				Event::TemplateModule(PoeEvent::ClaimTransferred(from, to, _)) => {
+					self.remove(&to);
+					self.add(from);
+				},
+				_ => {},
+			}
+		}
+	}
+
+	/// Move from best block `best` to the new best block of `notification`, undoing the blocks it
+	/// retracts and applying those it enacts. Returns `None`, leaving the holdings untouched, if
+	/// the new best block does not follow from `best`.
+	fn advance<C, BA>(
+		&mut self,
+		client: &C,
+		best: Hash,
+		notification: &BlockImportNotification<Block>,
+	) -> sp_blockchain::Result<Option<Activity>>
+	where
+		C: StorageProvider<Block, BA>,
+		BA: Backend<Block>,
+	{
+		let mut activity = Activity::default();
+		match &notification.tree_route {
+			// The route leads from the previous best block to the parent of the new one.
+			Some(route) => {
+				let start = route.retracted().first().unwrap_or_else(|| route.common_block());
+				if start.hash != best {
+					return Ok(None)
+				}
+				for block in route.retracted() {
+					self.revert(block_events(client, block.hash)?);
+				}
+				for block in route.enacted() {
+					self.apply(block_events(client, block.hash)?, &mut activity);
+				}
+			},
+			None if notification.header.parent_hash != best => return Ok(None),
+			None => {},
+		}
+		self.apply(block_events(client, notification.hash)?, &mut activity);
+		Ok(Some(activity))
+	}
+
+	/// Count the claims of every owner in the storage of block `hash`.
+	fn load<C, BA>(client: &C, hash: Hash) -> sp_blockchain::Result<Self>
+	where
+		C: StorageProvider<Block, BA>,
+		BA: Backend<Block>,
+	{
+		let id = BlockId::Hash(hash);
+		let prefix = StorageKey(TemplateModule::proofs_prefix().to_vec());
+		let mut holdings = Holdings::default();
+		for key in client.storage_keys_iter(&id, Some(&prefix), None)? {
+			let owner = client
+				.storage(&id, &key)?
+				.and_then(|value| <(AccountId, BlockNumber)>::decode(&mut &value.0[..]).ok())
+				.map(|(owner, _)| owner);
+			if let Some(owner) = owner {
+				holdings.add(owner);
+			}
+		}
+		Ok(holdings)
+	}
+}
+
+/// What happened to claims in a block.
+#[derive(Debug, Default, PartialEq)]
+struct Activity {
+	created: u64,
+	revoked: u64,
+	transferred: u64,
+	imported: u64,
+	/// The length of every created or imported claim.
+	sizes: Vec<usize>,
+}
+
+/// Keep `metrics` up to date with every new best block imported by `client`.
+pub async fn run<C, BA>(client: Arc<C>, metrics: PoeMetrics)
+where
+	C: BlockchainEvents<Block> + StorageProvider<Block, BA> + HeaderBackend<Block>,
+	BA: Backend<Block>,
+{
+	let mut best = client.info().best_hash;
+	let mut holdings = match Holdings::load(&*client, best) {
+		Ok(holdings) => holdings,
+		Err(e) => {
+			log::warn!("Counting claims for metrics failed: {}", e);
+			return
+		},
+	};
+	metrics.report(&holdings, &Activity::default());
+
+	let mut imported = client.import_notification_stream();
+	while let Some(notification) = imported.next().await {
+		if !notification.is_new_best {
+			continue
+		}
+		let activity = match holdings.advance(&*client, best, &notification) {
+			Ok(Some(activity)) => activity,
+			outcome => {
+				if let Err(e) = outcome {
+					log::warn!("Following {} for metrics failed: {}", notification.hash, e);
+				}
+				// The events in between are unknown: count the claims again.
+				match Holdings::load(&*client, notification.hash) {
+					Ok(recounted) => holdings = recounted,
+					Err(e) => log::warn!("Recounting claims for metrics failed: {}", e),
+				}
+				Activity::default()
+			},
+		};
+		best = notification.hash;
+		metrics.report(&holdings, &activity);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::Runtime;
+	use pallet_template::Event as PoeEvent;
+	use sp_core::crypto::AccountId32;
+
+	fn account(seed: u8) -> AccountId {
+		AccountId32::new([seed; 32])
+	}
+
+	fn poe(event: PoeEvent<Runtime>) -> Event {
+		Event::TemplateModule(event)
+	}
+
+	#[test]
+	fn events_update_holdings() {
+		let mut holdings = Holdings::default();
+		let mut activity = Activity::default();
+		holdings.apply(
+			vec![
+				poe(PoeEvent::ClaimCreated(account(1), vec![0; 8])),
+				poe(PoeEvent::ClaimCreated(account(1), vec![1; 16])),
+				poe(PoeEvent::ClaimImported(account(2), vec![2; 32], 1_600_000_000_000)),
+				Event::System(frame_system::Event::CodeUpdated),
+			],
+			&mut activity,
+		);
+
+		assert_eq!(
+			activity,
+			Activity { created: 2, imported: 1, sizes: vec![8, 16, 32], ..Default::default() }
+		);
+		assert_eq!((holdings.claims(), holdings.owners()), (3, 2));
+
+		let mut activity = Activity::default();
+		let block = vec![
+			poe(PoeEvent::ClaimTransferred(account(2), account(3), vec![2; 32])),
+			poe(PoeEvent::ClaimRevoked(account(1), vec![0; 8])),
+		];
+		holdings.apply(block.clone(), &mut activity);
+
+		assert_eq!(activity, Activity { revoked: 1, transferred: 1, ..Default::default() });
+		assert_eq!(holdings.0, vec![(account(1), 1), (account(3), 1)].into_iter().collect());
+
+		// Retracting the block restores the holdings from before it.
+		holdings.revert(block);
+		assert_eq!(holdings.0, vec![(account(1), 2), (account(2), 1)].into_iter().collect());
+	}
+
+	#[test]
+	fn metrics_report_holdings_and_activity() {
+		let registry = Registry::new();
+		let metrics = PoeMetrics::register(&registry).unwrap();
+		let mut holdings = Holdings::default();
+		let mut activity = Activity::default();
+		holdings.apply(
+			vec![
+				poe(PoeEvent::ClaimCreated(account(1), vec![0; 8])),
+				poe(PoeEvent::ClaimCreated(account(2), vec![0; 40])),
+			],
+			&mut activity,
+		);
+
+		metrics.report(&holdings, &activity);
+
+		assert_eq!(metrics.claims.get(), 2);
+		assert_eq!(metrics.owners.get(), 2);
+		assert_eq!(metrics.claim_events.with_label_values(&["created"]).get(), 2);
+		assert_eq!(metrics.claim_events.with_label_values(&["revoked"]).get(), 0);
+		assert_eq!(metrics.claim_size.get_sample_count(), 2);
+		assert_eq!(metrics.claim_size.get_sample_sum(), 48.0);
+
+		// Events accumulate across blocks, whether or not they were scraped in between.
+		let mut activity = Activity::default();
+		holdings.apply(vec![poe(PoeEvent::ClaimRevoked(account(1), vec![0; 8]))], &mut activity);
+		metrics.report(&holdings, &activity);
+		metrics.report(&holdings, &Activity::default());
+
+		assert_eq!(metrics.claims.get(), 1);
+		assert_eq!(metrics.claim_events.with_label_values(&["created"]).get(), 2);
+		assert_eq!(metrics.claim_events.with_label_values(&["revoked"]).get(), 1);
+	}
+}
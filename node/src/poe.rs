@@ -0,0 +1,490 @@
+//! The `poe` subcommands, notarizing files through a running node's RPC.
+//!
+//! Files are claimed by their BLAKE2-256 digest, which fits the runtime's claim length limits.
+//!
+//! For keys kept on air-gapped machines, `poe build` prepares an [`UnsignedTransaction`] on a
+//! connected machine, `poe sign` signs it offline and `poe broadcast` submits the result. Both
+//! are passed around as hex-encoded SCALE.
+//!
+//! `poe export` is the exception: it reads the claims from the node's database instead, see
+//! [`crate::export`].
+
+use crate::certificate::Certificate;
+use codec::{Decode, Encode};
+use futures::Future;
+use jsonrpc_core_client::{transports::ws, RpcError, TypedClient};
+use node_template_runtime::{
+	pallet_template, AccountId, Call, Hash, Header, Index, Runtime, SignedExtra, UncheckedExtrinsic,
+};
+use sc_cli::{Result, RuntimeVersion};
+use sp_core::{hexdisplay::HexDisplay, sr25519, Bytes, Pair};
+use sp_runtime::generic::{Era, SignedPayload};
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+/// Where a node listens for WebSocket RPC by default.
+const DEFAULT_URL: &str = "ws://127.0.0.1:9944";
+
+/// The `poe` command family.
+#[derive(Debug, StructOpt)]
+pub enum PoeCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	Client(PoeClientCmd),
+	/// Export all claims at a block from the node's database.
+	Export(crate::export::ExportCmd),
+}
+
+/// The `poe` commands that run without the node's database, offline or against a running node.
+#[derive(Debug, StructOpt)]
+pub enum PoeClientCmd {
+	/// Print the digest of a file, which is what gets claimed.
+	Hash(HashCmd),
+	/// Claim a file.
+	Claim(SubmitCmd),
+	/// Revoke the claim of a file.
+	Revoke(SubmitCmd),
+	/// Show who claimed a file and since when.
+	Status(StatusCmd),
+	/// Build an unsigned claim or revocation, to be signed offline.
+	Build(BuildCmd),
+	/// Sign a transaction built by `poe build`, without connecting to a node.
+	Sign(SignCmd),
+	/// Submit a transaction signed by `poe sign`.
+	Broadcast(BroadcastCmd),
+	/// Import claims from a legacy registry, as the sudo key.
+	Import(crate::import::ImportCmd),
+}
+
+impl PoeClientCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		match self {
+			PoeClientCmd::Hash(cmd) => cmd.run(),
+			PoeClientCmd::Claim(cmd) =>
+				cmd.run(|digest| pallet_template::Call::<Runtime>::create_claim(digest).into()),
+			PoeClientCmd::Revoke(cmd) =>
+				cmd.run(|digest| pallet_template::Call::<Runtime>::revoke_claim(digest).into()),
+			PoeClientCmd::Status(cmd) => cmd.run(),
+			PoeClientCmd::Build(cmd) => cmd.run(),
+			PoeClientCmd::Sign(cmd) => cmd.run(),
+			PoeClientCmd::Broadcast(cmd) => cmd.run(),
+			PoeClientCmd::Import(cmd) => cmd.run(),
+		}
+	}
+}
+
+/// Connection to the node.
+#[derive(Debug, StructOpt)]
+pub struct RpcParams {
+	/// WebSocket RPC endpoint of the node.
+	#[structopt(long, value_name = "URL", default_value = DEFAULT_URL)]
+	pub url: String,
+}
+
+impl RpcParams {
+	/// Run `f` with a client connected to the node.
+	pub fn with_client<T, F, Fut>(&self, f: F) -> Result<T>
+	where
+		F: FnOnce(TypedClient) -> Fut,
+		Fut: Future<Output = std::result::Result<T, RpcError>>,
+	{
+		futures::executor::block_on(async {
+			let client = ws::try_connect::<TypedClient>(&self.url)
+				.map_err(|e| format!("Invalid node URL {:?}: {}", self.url, e))?
+				.await
+				.map_err(|e| format!("Could not connect to {}: {}", self.url, e))?;
+			f(client).await.map_err(|e| format!("RPC request failed: {}", e).into())
+		})
+	}
+}
+
+/// The `poe hash` command.
+#[derive(Debug, StructOpt)]
+pub struct HashCmd {
+	/// The file to hash.
+	#[structopt(parse(from_os_str))]
+	pub file: PathBuf,
+}
+
+impl HashCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		println!("0x{}", HexDisplay::from(&file_digest(&self.file)?));
+		Ok(())
+	}
+}
+
+/// The `poe claim` and `poe revoke` commands.
+#[derive(Debug, StructOpt)]
+pub struct SubmitCmd {
+	/// The file whose digest is claimed or revoked.
+	#[structopt(parse(from_os_str))]
+	pub file: PathBuf,
+
+	/// Secret URI of the sr25519 key signing the extrinsic, e.g. `//Alice`.
+	#[structopt(long, value_name = "SURI")]
+	pub suri: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub rpc: RpcParams,
+}
+
+impl SubmitCmd {
+	/// Sign and submit the call built by `call` from the digest of the file.
+	fn run(&self, call: impl FnOnce(Vec<u8>) -> Call) -> Result<()> {
+		let digest = file_digest(&self.file)?;
+		let pair = sr25519::Pair::from_string(&self.suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+		let account = AccountId::from(pair.public());
+		let call = call(digest.to_vec());
+
+		let hash = self.rpc.with_client(|client| async move {
+			let info = ChainInfo::fetch(&client, &account, Some(DEFAULT_MORTALITY)).await?;
+			let extrinsic = sign(call, &pair, &info);
+			submit(&client, &extrinsic).await
+		})?;
+
+		println!("Submitted for 0x{} as {:?}.", HexDisplay::from(&digest), hash);
+		Ok(())
+	}
+}
+
+/// The `poe status` command.
+#[derive(Debug, StructOpt)]
+pub struct StatusCmd {
+	/// The file whose claim is looked up.
+	#[structopt(parse(from_os_str))]
+	pub file: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub rpc: RpcParams,
+}
+
+impl StatusCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let digest = file_digest(&self.file)?;
+		let certificate: Certificate = self.rpc.with_client(|client| async move {
+			client
+				.call_method(
+					"poe_certificate",
+					"PoeCertificate",
+					(Bytes(digest.to_vec()), Option::<Hash>::None),
+				)
+				.await
+		})?;
+
+		let hash = HexDisplay::from(&digest);
+		match certificate.claim {
+			Some((owner, block)) => {
+				println!("0x{} is claimed by {} since block #{}.", hash, owner, block);
+				if let Some(name) = certificate.owner_identity {
+					println!("The owner's verified identity is \"{}\".", name);
+				}
+			},
+			None => println!("0x{} is not claimed.", hash),
+		}
+		Ok(())
+	}
+}
+
+/// The `poe build` command.
+#[derive(Debug, StructOpt)]
+pub struct BuildCmd {
+	/// The file whose digest is claimed or revoked.
+	#[structopt(parse(from_os_str))]
+	pub file: PathBuf,
+
+	/// The account that is going to sign the transaction.
+	#[structopt(long, value_name = "ACCOUNT")]
+	pub signer: AccountId,
+
+	/// Revoke the claim instead of creating it.
+	#[structopt(long)]
+	pub revoke: bool,
+
+	/// Number of blocks the transaction stays valid for, rounded up to a power of two.
+	///
+	/// Defaults to 64 blocks, so that a transaction which is not signed and submitted in time
+	/// cannot be included much later.
+	#[structopt(long, value_name = "BLOCKS")]
+	pub mortality: Option<u64>,
+
+	/// Make the transaction valid until it is included, however long that takes.
+	#[structopt(long, conflicts_with = "mortality")]
+	pub immortal: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub rpc: RpcParams,
+}
+
+impl BuildCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let digest = file_digest(&self.file)?.to_vec();
+		let call: Call = if self.revoke {
+			pallet_template::Call::<Runtime>::revoke_claim(digest).into()
+		} else {
+			pallet_template::Call::<Runtime>::create_claim(digest).into()
+		};
+
+		let signer = self.signer.clone();
+		let mortality =
+			if self.immortal { None } else { Some(self.mortality.unwrap_or(DEFAULT_MORTALITY)) };
+		let info = self.rpc.with_client(|client| async move {
+			ChainInfo::fetch(&client, &signer, mortality).await
+		})?;
+
+		let transaction = UnsignedTransaction { signer: self.signer.clone(), call, info };
+		println!("0x{}", HexDisplay::from(&transaction.encode()));
+		Ok(())
+	}
+}
+
+/// The `poe sign` command.
+#[derive(Debug, StructOpt)]
+pub struct SignCmd {
+	/// File with the output of `poe build`.
+	#[structopt(parse(from_os_str))]
+	pub transaction: PathBuf,
+
+	/// Secret URI of the sr25519 key signing the transaction.
+	#[structopt(long, value_name = "SURI")]
+	pub suri: String,
+}
+
+impl SignCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let transaction: UnsignedTransaction = read_hex(&self.transaction)?;
+		let pair = sr25519::Pair::from_string(&self.suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+		if AccountId::from(pair.public()) != transaction.signer {
+			return Err(format!(
+				"The transaction was built for {}, not for the given key",
+				transaction.signer
+			)
+			.into())
+		}
+
+		// Show what is being signed, keeping stdout for the signed transaction.
+		eprintln!("Signing {:?} with nonce {}.", transaction.call, transaction.info.nonce);
+		let extrinsic = sign(transaction.call, &pair, &transaction.info);
+		println!("0x{}", HexDisplay::from(&extrinsic.encode()));
+		Ok(())
+	}
+}
+
+/// The `poe broadcast` command.
+#[derive(Debug, StructOpt)]
+pub struct BroadcastCmd {
+	/// File with the output of `poe sign`.
+	#[structopt(parse(from_os_str))]
+	pub extrinsic: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub rpc: RpcParams,
+}
+
+impl BroadcastCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let extrinsic: UncheckedExtrinsic = read_hex(&self.extrinsic)?;
+		let hash = self
+			.rpc
+			.with_client(|client| async move { submit(&client, &extrinsic).await })?;
+		println!("Submitted as {:?}.", hash);
+		Ok(())
+	}
+}
+
+/// Read a hex-encoded SCALE value from the file at `path`.
+fn read_hex<T: Decode>(path: &Path) -> Result<T> {
+	let raw = fs::read_to_string(path)
+		.map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+	let bytes = sp_core::bytes::from_hex(raw.trim())
+		.map_err(|e| format!("Invalid hex in {}: {:?}", path.display(), e))?;
+	Ok(Decode::decode(&mut &bytes[..])?)
+}
+
+/// Number of blocks the transactions of the `poe` commands stay valid for by default.
+pub const DEFAULT_MORTALITY: u64 = 64;
+
+/// The digest of the file at `path`, as claimed by the `poe` commands.
+pub fn file_digest(path: &Path) -> Result<[u8; 32]> {
+	let contents =
+		fs::read(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+	Ok(sp_core::hashing::blake2_256(&contents))
+}
+
+/// Everything about the chain and the signer that a signed extrinsic commits to.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct ChainInfo {
+	/// Hash of the genesis block.
+	pub genesis_hash: Hash,
+	/// `spec_version` of the runtime.
+	pub spec_version: u32,
+	/// `transaction_version` of the runtime.
+	pub transaction_version: u32,
+	/// Nonce of the signer.
+	pub nonce: Index,
+	/// Period during which the transaction is valid.
+	pub era: Era,
+	/// Hash of the block `era` starts at, or the genesis hash if it is immortal.
+	pub era_hash: Hash,
+}
+
+impl ChainInfo {
+	/// Query the node for the chain's current state and the next nonce of `account`.
+	///
+	/// The transaction is valid for `mortality` blocks from the best block, or forever.
+	pub async fn fetch(
+		client: &TypedClient,
+		account: &AccountId,
+		mortality: Option<u64>,
+	) -> std::result::Result<Self, RpcError> {
+		let genesis_hash: Hash = client.call_method("chain_getBlockHash", "Hash", (0,)).await?;
+		let version: RuntimeVersion =
+			client.call_method("state_getRuntimeVersion", "RuntimeVersion", ()).await?;
+		let nonce: Index =
+			client.call_method("system_accountNextIndex", "Index", (account,)).await?;
+		let (era, era_hash) = match mortality {
+			Some(period) => {
+				let best: Header = client.call_method("chain_getHeader", "Header", ()).await?;
+				let era = Era::mortal(period, best.number.into());
+				let birth = era.birth(best.number.into());
+				let hash = client.call_method("chain_getBlockHash", "Hash", (birth,)).await?;
+				(era, hash)
+			},
+			None => (Era::Immortal, genesis_hash),
+		};
+		Ok(ChainInfo {
+			genesis_hash,
+			spec_version: version.spec_version,
+			transaction_version: version.transaction_version,
+			nonce,
+			era,
+			era_hash,
+		})
+	}
+}
+
+/// A call along with everything needed to sign it offline.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct UnsignedTransaction {
+	/// The account expected to sign the transaction.
+	pub signer: AccountId,
+	/// The call to dispatch.
+	pub call: Call,
+	/// What the signature commits to besides the call.
+	pub info: ChainInfo,
+}
+
+/// The runtime's signed extensions for a transaction with `nonce`, valid during `era`.
+pub fn signed_extra(nonce: Index, era: Era) -> SignedExtra {
+	(
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(era),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+	)
+}
+
+/// Sign `call` with `pair`, for the chain described by `info`.
+///
+/// The implicit data signed along the call has to match the runtime's `SignedExtra`.
+pub fn sign(call: Call, pair: &sr25519::Pair, info: &ChainInfo) -> UncheckedExtrinsic {
+	let extra = signed_extra(info.nonce, info.era);
+	let additional =
+		(info.spec_version, info.transaction_version, info.genesis_hash, info.era_hash, (), (), ());
+	let payload = SignedPayload::from_raw(call.clone(), extra.clone(), additional);
+	let signature = payload.using_encoded(|payload| pair.sign(payload));
+	UncheckedExtrinsic::new_signed(
+		call,
+		AccountId::from(pair.public()).into(),
+		signature.into(),
+		extra,
+	)
+}
+
+/// Submit `extrinsic` to the node's transaction pool, returning its hash.
+pub async fn submit(
+	client: &TypedClient,
+	extrinsic: &UncheckedExtrinsic,
+) -> std::result::Result<Hash, RpcError> {
+	client
+		.call_method("author_submitExtrinsic", "Hash", (Bytes(extrinsic.encode()),))
+		.await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::Signature;
+	use sp_runtime::{traits::Verify, MultiAddress};
+
+	fn chain_info() -> ChainInfo {
+		ChainInfo {
+			genesis_hash: Hash::repeat_byte(1),
+			spec_version: 100,
+			transaction_version: 1,
+			nonce: 7,
+			era: Era::mortal(64, 100),
+			era_hash: Hash::repeat_byte(2),
+		}
+	}
+
+	#[test]
+	fn extrinsics_are_signed_over_call_and_extra() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let account = AccountId::from(pair.public());
+		let call: Call = pallet_template::Call::<Runtime>::create_claim(vec![1; 32]).into();
+
+		let extrinsic = sign(call.clone(), &pair, &chain_info());
+		let (address, signature, extra) = extrinsic.signature.unwrap();
+
+		assert_eq!(address, MultiAddress::Id(account.clone()));
+		assert_eq!(extra.encode(), signed_extra(7, Era::mortal(64, 100)).encode());
+		let (genesis, birth) = (Hash::repeat_byte(1), Hash::repeat_byte(2));
+		let payload = (call, extra, (100u32, 1u32, genesis, birth, (), (), ())).encode();
+		assert!(Signature::verify(&signature, &payload[..], &account));
+	}
+
+	#[test]
+	fn unsigned_transactions_round_trip_through_hex_files() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let transaction = UnsignedTransaction {
+			signer: AccountId::from(pair.public()),
+			call: pallet_template::Call::<Runtime>::revoke_claim(vec![1; 32]).into(),
+			info: chain_info(),
+		};
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("unsigned.hex");
+		fs::write(&path, format!("0x{}\n", HexDisplay::from(&transaction.encode()))).unwrap();
+
+		assert_eq!(read_hex::<UnsignedTransaction>(&path).unwrap(), transaction);
+		fs::write(&path, "0x00").unwrap();
+		assert!(read_hex::<UnsignedTransaction>(&path).is_err());
+	}
+
+	#[test]
+	fn file_digest_is_blake2_256_of_the_contents() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("document.txt");
+		fs::write(&path, b"hello").unwrap();
+
+		assert_eq!(file_digest(&path).unwrap(), sp_core::hashing::blake2_256(b"hello"));
+		assert!(file_digest(&dir.path().join("missing")).is_err());
+	}
+}
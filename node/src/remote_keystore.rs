@@ -0,0 +1,687 @@
+//! A keystore that keeps no keys itself but forwards every operation to a separate signer
+//! process over a Unix socket.
+//!
+//! The protocol is one JSON [`Request`] per connection, answered by one JSON [`Response`], each
+//! terminated by a newline. [`serve`] implements the signer side on top of any local keystore and
+//! backs the `signer` subcommand.
+//!
+//! The signer can make the validator sign anything, so its socket is only accessible to the user
+//! running it, and on Linux connections from other users are refused as well.
+
+use futures::channel::oneshot;
+use sc_cli::Result as CliResult;
+use sc_keystore::LocalKeystore;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId, SecretString},
+	ecdsa, ed25519, sr25519, Bytes,
+};
+use sp_keystore::{
+	vrf::{VRFSignature, VRFTranscriptData},
+	CryptoStore, Error, SyncCryptoStore, SyncCryptoStorePtr,
+};
+use std::{
+	convert::TryFrom,
+	fs,
+	io::{self, BufRead, BufReader, Read, Write},
+	os::unix::{
+		fs::PermissionsExt,
+		net::{UnixListener, UnixStream},
+	},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
+use structopt::StructOpt;
+
+/// How long either side waits for the other before giving up on a request.
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest request the signer reads, which leaves ample room for the messages to sign.
+const MAX_REQUEST_LEN: u64 = 1024 * 1024;
+
+/// A request to the signer.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+	/// List the public keys of `crypto` under `key_type`.
+	PublicKeys { key_type: String, crypto: String },
+	/// Generate a new key of `crypto` under `key_type`.
+	GenerateNew { key_type: String, crypto: String, seed: Option<String> },
+	/// Insert a key given by its secret URI.
+	InsertUnknown { key_type: String, suri: String, public: Bytes },
+	/// List all keys under `key_type`.
+	Keys { key_type: String },
+	/// Whether all the given `(public, key_type)` pairs are present.
+	HasKeys { keys: Vec<(Bytes, String)> },
+	/// Sign `message` with the `crypto` key `public` under `key_type`.
+	SignWith { key_type: String, crypto: String, public: Bytes, message: Bytes },
+}
+
+/// A response from the signer.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+	/// Public keys, for `PublicKeys` and `GenerateNew`.
+	PublicKeys(Vec<Bytes>),
+	/// `(crypto, public)` pairs, for `Keys`.
+	Keys(Vec<(String, Bytes)>),
+	/// Answer to `HasKeys`.
+	HasKeys(bool),
+	/// Signature, or `None` if the key is not known, for `SignWith`.
+	Signature(Option<Bytes>),
+	/// The request succeeded without a result.
+	Ok,
+	/// The request failed.
+	Error(String),
+}
+
+/// Four character codes such as `aura` or `sr25` are sent as plain strings.
+fn four_cc(id: [u8; 4]) -> String {
+	String::from_utf8_lossy(&id).into_owned()
+}
+
+fn parse_four_cc(s: &str) -> Result<[u8; 4], Error> {
+	<[u8; 4]>::try_from(s.as_bytes())
+		.map_err(|_| Error::ValidationError(format!("Invalid four character code {:?}", s)))
+}
+
+/// A [`SyncCryptoStore`] backed by a remote signer.
+#[derive(Clone)]
+pub struct RemoteKeystore {
+	socket: PathBuf,
+}
+
+impl RemoteKeystore {
+	/// Connect to the signer at `url`, either `unix://<path>` or a plain socket path.
+	pub fn open(url: &str) -> Result<Self, String> {
+		if url.contains("://") && !url.starts_with("unix://") {
+			return Err(format!("Unsupported remote keystore {}, expected unix://<path>", url))
+		}
+		let keystore = Self { socket: PathBuf::from(url.trim_start_matches("unix://")) };
+		// Fail early rather than on the first signing request.
+		keystore.call(&Request::Keys { key_type: four_cc(*b"test") }).map_err(|e| {
+			format!("Signer at {} is not reachable: {}", keystore.socket.display(), e)
+		})?;
+		Ok(keystore)
+	}
+
+	fn call(&self, request: &Request) -> Result<Response, Error> {
+		let io_error = |e: io::Error| Error::Other(format!("Remote signer: {}", e));
+
+		let mut stream = UnixStream::connect(&self.socket).map_err(io_error)?;
+		stream.set_read_timeout(Some(SIGNER_TIMEOUT)).map_err(io_error)?;
+		stream.set_write_timeout(Some(SIGNER_TIMEOUT)).map_err(io_error)?;
+
+		let mut line = serde_json::to_string(request).map_err(|e| Error::Other(e.to_string()))?;
+		line.push('\n');
+		stream.write_all(line.as_bytes()).map_err(io_error)?;
+
+		let mut reply = String::new();
+		BufReader::new(stream).read_line(&mut reply).map_err(io_error)?;
+		match serde_json::from_str(&reply).map_err(|e| Error::Other(e.to_string()))? {
+			Response::Error(e) => Err(Error::Other(e)),
+			response => Ok(response),
+		}
+	}
+
+	fn public_keys(&self, id: KeyTypeId, crypto: CryptoTypeId) -> Vec<Vec<u8>> {
+		let request = Request::PublicKeys { key_type: four_cc(id.0), crypto: four_cc(crypto.0) };
+		match self.call(&request) {
+			Ok(Response::PublicKeys(keys)) => keys.into_iter().map(|k| k.0).collect(),
+			Ok(response) => {
+				log::warn!("Unexpected response from remote signer: {:?}", response);
+				Vec::new()
+			},
+			Err(e) => {
+				log::warn!("Listing keys on remote signer failed: {}", e);
+				Vec::new()
+			},
+		}
+	}
+
+	fn generate_new(
+		&self,
+		id: KeyTypeId,
+		crypto: CryptoTypeId,
+		seed: Option<&str>,
+	) -> Result<Vec<u8>, Error> {
+		let request = Request::GenerateNew {
+			key_type: four_cc(id.0),
+			crypto: four_cc(crypto.0),
+			seed: seed.map(Into::into),
+		};
+		match self.call(&request)? {
+			Response::PublicKeys(mut keys) if keys.len() == 1 => Ok(keys.remove(0).0),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	/// Run `f` on a thread of its own, so that waiting for the signer does not block the async
+	/// executor.
+	async fn blocking<R, F>(&self, f: F) -> Result<R, Error>
+	where
+		F: FnOnce(&RemoteKeystore) -> R + Send + 'static,
+		R: Send + 'static,
+	{
+		let keystore = self.clone();
+		let (sender, receiver) = oneshot::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(f(&keystore));
+		});
+		receiver.await.map_err(|_| Error::Other("Remote signer request was dropped".into()))
+	}
+}
+
+fn unexpected(response: Response) -> Error {
+	Error::Other(format!("Unexpected response from remote signer: {:?}", response))
+}
+
+fn from_raw<T: for<'a> TryFrom<&'a [u8]>>(raw: Vec<u8>) -> Result<T, Error> {
+	T::try_from(&raw[..]).map_err(|_| Error::ValidationError("Invalid public key".into()))
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, sr25519::CRYPTO_ID)
+			.into_iter()
+			.filter_map(|k| from_raw(k).ok())
+			.collect()
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		from_raw(self.generate_new(id, sr25519::CRYPTO_ID, seed)?)
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, ed25519::CRYPTO_ID)
+			.into_iter()
+			.filter_map(|k| from_raw(k).ok())
+			.collect()
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		from_raw(self.generate_new(id, ed25519::CRYPTO_ID, seed)?)
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, ecdsa::CRYPTO_ID)
+			.into_iter()
+			.filter_map(|k| from_raw(k).ok())
+			.collect()
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		from_raw(self.generate_new(id, ecdsa::CRYPTO_ID, seed)?)
+	}
+
+	fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		let request = Request::InsertUnknown {
+			key_type: four_cc(id.0),
+			suri: suri.into(),
+			public: public.to_vec().into(),
+		};
+		match self.call(&request) {
+			Ok(Response::Ok) => Ok(()),
+			_ => Err(()),
+		}
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let known = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| known.contains(key)).collect())
+	}
+
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		match self.call(&Request::Keys { key_type: four_cc(id.0) })? {
+			Response::Keys(keys) => keys
+				.into_iter()
+				.map(|(crypto, public)| {
+					Ok(CryptoTypePublicPair(CryptoTypeId(parse_four_cc(&crypto)?), public.0))
+				})
+				.collect(),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let keys = public_keys
+			.iter()
+			.map(|(public, id)| (public.clone().into(), four_cc(id.0)))
+			.collect();
+		matches!(self.call(&Request::HasKeys { keys }), Ok(Response::HasKeys(true)))
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let request = Request::SignWith {
+			key_type: four_cc(id.0),
+			crypto: four_cc((key.0).0),
+			public: key.1.clone().into(),
+			message: msg.to_vec().into(),
+		};
+		match self.call(&request)? {
+			Response::Signature(signature) => Ok(signature.map(|s| s.0)),
+			response => Err(unexpected(response)),
+		}
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		_key_type: KeyTypeId,
+		_public: &sr25519::Public,
+		_transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		Err(Error::Other("VRF signing is not supported by the remote signer".into()))
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		_id: KeyTypeId,
+		_public: &ecdsa::Public,
+		_msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		Err(Error::Other("Prehashed ECDSA signing is not supported by the remote signer".into()))
+	}
+}
+
+#[async_trait::async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.blocking(move |k| SyncCryptoStore::sr25519_public_keys(k, id))
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		let seed = seed.map(String::from);
+		self.blocking(move |k| SyncCryptoStore::sr25519_generate_new(k, id, seed.as_deref()))
+			.await?
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.blocking(move |k| SyncCryptoStore::ed25519_public_keys(k, id))
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		let seed = seed.map(String::from);
+		self.blocking(move |k| SyncCryptoStore::ed25519_generate_new(k, id, seed.as_deref()))
+			.await?
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.blocking(move |k| SyncCryptoStore::ecdsa_public_keys(k, id))
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		let seed = seed.map(String::from);
+		self.blocking(move |k| SyncCryptoStore::ecdsa_generate_new(k, id, seed.as_deref()))
+			.await?
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		let (suri, public) = (suri.to_string(), public.to_vec());
+		self.blocking(move |k| SyncCryptoStore::insert_unknown(k, id, &suri, &public))
+			.await
+			.unwrap_or(Err(()))
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.blocking(move |k| SyncCryptoStore::supported_keys(k, id, keys)).await?
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.blocking(move |k| SyncCryptoStore::keys(k, id)).await?
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let public_keys = public_keys.to_vec();
+		self.blocking(move |k| SyncCryptoStore::has_keys(k, &public_keys))
+			.await
+			.unwrap_or(false)
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let (key, msg) = (key.clone(), msg.to_vec());
+		self.blocking(move |k| SyncCryptoStore::sign_with(k, id, &key, &msg)).await?
+	}
+
+	// Neither is supported, so there is no request to wait for.
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		SyncCryptoStore::sr25519_vrf_sign(self, key_type, public, transcript_data)
+	}
+
+	async fn ecdsa_sign_prehashed(
+		&self,
+		id: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		SyncCryptoStore::ecdsa_sign_prehashed(self, id, public, msg)
+	}
+}
+
+fn to_bytes<K: AsRef<[u8]>>(keys: Vec<K>) -> Vec<Bytes> {
+	keys.iter().map(|k| k.as_ref().to_vec().into()).collect()
+}
+
+/// Answer a single request from `keystore`.
+fn handle(keystore: &dyn SyncCryptoStore, request: Request) -> Result<Response, Error> {
+	let key_type = |s: &str| parse_four_cc(s).map(KeyTypeId);
+	let crypto = |s: &str| parse_four_cc(s).map(CryptoTypeId);
+
+	Ok(match request {
+		Request::PublicKeys { key_type: id, crypto: c } => {
+			let id = key_type(&id)?;
+			Response::PublicKeys(match crypto(&c)? {
+				sr25519::CRYPTO_ID => to_bytes(SyncCryptoStore::sr25519_public_keys(keystore, id)),
+				ed25519::CRYPTO_ID => to_bytes(SyncCryptoStore::ed25519_public_keys(keystore, id)),
+				ecdsa::CRYPTO_ID => to_bytes(SyncCryptoStore::ecdsa_public_keys(keystore, id)),
+				_ => return Err(Error::ValidationError(format!("Unknown crypto {}", c))),
+			})
+		},
+		Request::GenerateNew { key_type: id, crypto: c, seed } => {
+			let (id, seed) = (key_type(&id)?, seed.as_deref());
+			Response::PublicKeys(match crypto(&c)? {
+				sr25519::CRYPTO_ID =>
+					to_bytes(vec![SyncCryptoStore::sr25519_generate_new(keystore, id, seed)?]),
+				ed25519::CRYPTO_ID =>
+					to_bytes(vec![SyncCryptoStore::ed25519_generate_new(keystore, id, seed)?]),
+				ecdsa::CRYPTO_ID =>
+					to_bytes(vec![SyncCryptoStore::ecdsa_generate_new(keystore, id, seed)?]),
+				_ => return Err(Error::ValidationError(format!("Unknown crypto {}", c))),
+			})
+		},
+		Request::InsertUnknown { key_type: id, suri, public } => {
+			SyncCryptoStore::insert_unknown(keystore, key_type(&id)?, &suri, &public)
+				.map_err(|()| Error::Other("Inserting key failed".into()))?;
+			Response::Ok
+		},
+		Request::Keys { key_type: id } => Response::Keys(
+			SyncCryptoStore::keys(keystore, key_type(&id)?)?
+				.into_iter()
+				.map(|CryptoTypePublicPair(c, public)| (four_cc(c.0), public.into()))
+				.collect(),
+		),
+		Request::HasKeys { keys } => {
+			let keys = keys
+				.into_iter()
+				.map(|(public, id)| Ok((public.0, key_type(&id)?)))
+				.collect::<Result<Vec<_>, Error>>()?;
+			Response::HasKeys(SyncCryptoStore::has_keys(keystore, &keys))
+		},
+		Request::SignWith { key_type: id, crypto: c, public, message } => {
+			let key = CryptoTypePublicPair(crypto(&c)?, public.0);
+			let signature = SyncCryptoStore::sign_with(keystore, key_type(&id)?, &key, &message)?;
+			Response::Signature(signature.map(Into::into))
+		},
+	})
+}
+
+/// The user id of the process at the other end of `stream`.
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> io::Result<libc::uid_t> {
+	use std::os::unix::io::AsRawFd;
+
+	let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+	let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+	// SAFETY: `credentials` and `len` are valid for writes and `len` is the size of `credentials`.
+	let result = unsafe {
+		libc::getsockopt(
+			stream.as_raw_fd(),
+			libc::SOL_SOCKET,
+			libc::SO_PEERCRED,
+			&mut credentials as *mut libc::ucred as *mut libc::c_void,
+			&mut len,
+		)
+	};
+	if result != 0 {
+		return Err(io::Error::last_os_error())
+	}
+	Ok(credentials.uid)
+}
+
+/// Refuse connections from other users, in case the socket was made accessible to them.
+#[cfg(target_os = "linux")]
+fn check_peer(stream: &UnixStream) -> io::Result<()> {
+	let (peer, us) = (peer_uid(stream)?, unsafe { libc::geteuid() });
+	if peer != us {
+		let e = format!("Refusing connection from user {}, expected user {}", peer, us);
+		return Err(io::Error::new(io::ErrorKind::PermissionDenied, e))
+	}
+	Ok(())
+}
+
+/// Other platforms rely on the permissions of the socket alone.
+#[cfg(not(target_os = "linux"))]
+fn check_peer(_stream: &UnixStream) -> io::Result<()> {
+	Ok(())
+}
+
+fn handle_connection(keystore: &dyn SyncCryptoStore, stream: UnixStream) -> io::Result<()> {
+	check_peer(&stream)?;
+	// A client that stalls or never ends its request must not hold on to the connection.
+	stream.set_read_timeout(Some(SIGNER_TIMEOUT))?;
+	stream.set_write_timeout(Some(SIGNER_TIMEOUT))?;
+
+	let mut line = String::new();
+	BufReader::new((&stream).take(MAX_REQUEST_LEN)).read_line(&mut line)?;
+
+	let response = match serde_json::from_str(&line) {
+		Ok(request) => handle(keystore, request).unwrap_or_else(|e| Response::Error(e.to_string())),
+		Err(e) => Response::Error(format!("Invalid request: {}", e)),
+	};
+
+	let mut reply = serde_json::to_string(&response)?;
+	reply.push('\n');
+	(&stream).write_all(reply.as_bytes())
+}
+
+/// Serve signing requests for `keystore` on `listener` until it fails.
+///
+/// Every connection is handled on a thread of its own, so a slow client does not hold up the
+/// signing requests of others.
+pub fn serve(keystore: SyncCryptoStorePtr, listener: UnixListener) -> io::Result<()> {
+	for stream in listener.incoming() {
+		let (keystore, stream) = (keystore.clone(), stream?);
+		std::thread::spawn(move || {
+			if let Err(e) = handle_connection(&*keystore, stream) {
+				log::warn!("Remote signer connection failed: {}", e);
+			}
+		});
+	}
+	Ok(())
+}
+
+/// Bind a socket at `path` that only the current user may connect to.
+fn bind_private(path: &Path) -> io::Result<UnixListener> {
+	// On Linux, clients of other users connecting before the permissions are restricted are
+	// still turned away by `check_peer`.
+	let listener = UnixListener::bind(path)?;
+	fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+	Ok(listener)
+}
+
+/// The `signer` command, serving the keys of a local keystore to nodes started with
+/// `--keystore-uri unix://<socket>`.
+#[derive(Debug, StructOpt)]
+pub struct SignerCmd {
+	/// Path of the Unix socket to listen on.
+	#[structopt(long, parse(from_os_str))]
+	pub socket: PathBuf,
+
+	/// Path of the local keystore holding the keys.
+	#[structopt(long, parse(from_os_str))]
+	pub keystore_path: PathBuf,
+
+	/// File holding the password of the keystore.
+	///
+	/// The password is not taken on the command line, where other users could read it.
+	#[structopt(long, parse(from_os_str))]
+	pub password_filename: Option<PathBuf>,
+}
+
+impl SignerCmd {
+	/// Run the command.
+	pub fn run(&self) -> CliResult<()> {
+		let password = match &self.password_filename {
+			Some(path) => {
+				let password = std::fs::read_to_string(path)
+					.map_err(|e| format!("Cannot read password file: {}", e))?;
+				Some(SecretString::new(password.trim_end_matches(&['\r', '\n'][..]).into()))
+			},
+			None => None,
+		};
+		let keystore = LocalKeystore::open(&self.keystore_path, password)
+			.map_err(|e| format!("Cannot open keystore: {}", e))?;
+
+		// A stale socket from a previous run would make binding fail.
+		if self.socket.exists() {
+			std::fs::remove_file(&self.socket)?;
+		}
+		let listener = bind_private(&self.socket)?;
+		println!("Serving {} on {}", self.keystore_path.display(), self.socket.display());
+
+		serve(Arc::new(keystore), listener)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{crypto::Pair as _, testing::ED25519, testing::SR25519};
+
+	const AURA: KeyTypeId = KeyTypeId(*b"aura");
+	const GRANDPA: KeyTypeId = KeyTypeId(*b"gran");
+
+	fn signer_stub() -> (tempfile::TempDir, SyncCryptoStorePtr, RemoteKeystore) {
+		let dir = tempfile::tempdir().unwrap();
+		let socket = dir.path().join("signer.sock");
+		let local: SyncCryptoStorePtr = Arc::new(LocalKeystore::in_memory());
+
+		let listener = bind_private(&socket).unwrap();
+		let served = local.clone();
+		std::thread::spawn(move || serve(served, listener));
+
+		let remote = RemoteKeystore::open(&format!("unix://{}", socket.display())).unwrap();
+		(dir, local, remote)
+	}
+
+	#[test]
+	fn remote_keystore_signs_aura_with_sr25519() {
+		let (_dir, local, remote) = signer_stub();
+		let public = SyncCryptoStore::sr25519_generate_new(&*local, AURA, None).unwrap();
+
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&remote, AURA), vec![public]);
+		assert!(SyncCryptoStore::sr25519_public_keys(&remote, SR25519).is_empty());
+
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, public.0.to_vec());
+		let signature = SyncCryptoStore::sign_with(&remote, AURA, &key, b"block").unwrap().unwrap();
+		let signature = sr25519::Signature::try_from(&signature[..]).unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"block", &public));
+	}
+
+	#[test]
+	fn remote_keystore_signs_grandpa_with_ed25519() {
+		let (_dir, local, remote) = signer_stub();
+		let public = SyncCryptoStore::ed25519_generate_new(&*local, GRANDPA, None).unwrap();
+
+		assert_eq!(SyncCryptoStore::ed25519_public_keys(&remote, GRANDPA), vec![public]);
+		assert!(SyncCryptoStore::ed25519_public_keys(&remote, ED25519).is_empty());
+		assert!(SyncCryptoStore::has_keys(&remote, &[(public.0.to_vec(), GRANDPA)]));
+
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		let signature =
+			SyncCryptoStore::sign_with(&remote, GRANDPA, &key, b"vote").unwrap().unwrap();
+		let signature = ed25519::Signature::try_from(&signature[..]).unwrap();
+		assert!(ed25519::Pair::verify(&signature, b"vote", &public));
+	}
+
+	#[test]
+	fn stalled_clients_do_not_block_signing() {
+		let (dir, local, remote) = signer_stub();
+		let public = SyncCryptoStore::sr25519_generate_new(&*local, AURA, None).unwrap();
+
+		// Connects and never sends a complete request.
+		let mut stalled = UnixStream::connect(dir.path().join("signer.sock")).unwrap();
+		stalled.write_all(b"{").unwrap();
+
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, public.0.to_vec());
+		assert!(SyncCryptoStore::sign_with(&remote, AURA, &key, b"block").unwrap().is_some());
+	}
+
+	#[test]
+	fn socket_is_private() {
+		let (dir, _local, _remote) = signer_stub();
+		let mode = std::fs::metadata(dir.path().join("signer.sock")).unwrap().permissions().mode();
+		assert_eq!(mode & 0o777, 0o600);
+	}
+
+	#[test]
+	fn async_requests_reach_the_signer() {
+		let (_dir, local, remote) = signer_stub();
+		let public = SyncCryptoStore::ed25519_generate_new(&*local, GRANDPA, None).unwrap();
+
+		let keys = futures::executor::block_on(CryptoStore::ed25519_public_keys(&remote, GRANDPA));
+		assert_eq!(keys, vec![public]);
+	}
+
+	#[test]
+	fn unknown_keys_are_not_signed_with() {
+		let (_dir, _local, remote) = signer_stub();
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, vec![0; 32]);
+
+		assert_eq!(SyncCryptoStore::sign_with(&remote, AURA, &key, b"block").unwrap(), None);
+	}
+}
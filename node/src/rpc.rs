@@ -7,7 +7,17 @@
 
 use std::sync::Arc;
 
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+mod claim_events;
+mod light_verify;
+mod poe_query;
+
+pub use claim_events::{ClaimEvent, ClaimEventFilter, ClaimEventKind, ClaimEventNotification};
+
+use futures::channel::mpsc::Sender;
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use sc_client_api::{light::Fetcher, Backend, BlockchainEvents, ProofProvider, StorageProvider};
+use sc_consensus_manual_seal::EngineCommand;
+use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -22,29 +32,73 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Manual seal command sink, when sealing blocks on demand.
+	pub command_sink: Option<Sender<EngineCommand<Hash>>>,
+	/// Executor of subscription tasks.
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// The claim index, when the node keeps one.
+	pub poe_index: Option<Arc<crate::indexer::PoeIndex>>,
+}
+
+/// Light client dependencies.
+pub struct LightDeps<C, F> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Remote access to the blockchain (async).
+	pub fetcher: Arc<F>,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+pub fn create_full<C, P, B>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
 where
+	B: Backend<Block> + Send + Sync + 'static,
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: ProofProvider<Block> + BlockchainEvents<Block> + StorageProvider<Block, B>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	C::Api: pallet_template_rpc::PoeRuntimeApi<Block, AccountId, BlockNumber>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
 {
+	use claim_events::{ClaimEvents, ClaimEventsApi};
+	use jsonrpc_pubsub::manager::SubscriptionManager;
+	use pallet_contracts_rpc::{Contracts, ContractsApi};
+	use pallet_template_rpc::{Poe, PoeApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use poe_query::{PoeQuery, PoeQueryApi};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, command_sink, subscription_executor, poe_index } =
+		deps;
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
 
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 
+	io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
+
+	io.extend_with(PoeApi::to_delegate(Poe::new(client.clone())));
+
+	io.extend_with(ClaimEventsApi::to_delegate(ClaimEvents::new(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(subscription_executor)),
+	)));
+
+	if let Some(index) = poe_index {
+		// `poe_queryEvents` and `poe_queryStatus`.
+		io.extend_with(PoeQueryApi::to_delegate(PoeQuery::new(index)));
+	}
+
+	if let Some(command_sink) = command_sink {
+		// `engine_createBlock` and `engine_finalizeBlock`.
+		io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	}
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -52,3 +106,20 @@ where
 
 	io
 }
+
+/// Instantiate all light RPC extensions.
+pub fn create_light<C, F>(deps: LightDeps<C, F>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + Send + Sync + 'static,
+	F::RemoteReadResult: Send + 'static,
+{
+	use light_verify::{LightVerify, LightVerifyApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let LightDeps { client, fetcher } = deps;
+
+	io.extend_with(LightVerifyApi::to_delegate(LightVerify::new(client, fetcher)));
+
+	io
+}
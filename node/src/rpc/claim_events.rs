@@ -0,0 +1,356 @@
+//! A subscription to the lifecycle events of claims, decoded from the events of each block.
+//!
+//! Every subscription follows the head of the chain it reports, the best or the finalized chain,
+//! from the head at the time it was made. When the head moves, the route between the old and the
+//! new head is walked, so blocks finalized together and blocks enacted by a re-organisation are
+//! all reported, in order. Blocks retracted by a re-organisation have their events sent again,
+//! newest first and marked as retracted.
+
+use std::{marker::PhantomData, pin::Pin, sync::Arc};
+
+use futures::{future, stream, FutureExt, SinkExt, Stream, StreamExt};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use node_template_runtime::{opaque::Block, pallet_template, AccountId, BlockNumber, Event, Hash};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::{HeaderBackend, HeaderMetadata};
+use sp_core::Bytes;
+use sp_runtime::traits::Header as _;
+
+use crate::events::block_events;
+
+/// What happened to a claim.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClaimEventKind {
+	/// The claim was created.
+	Created,
+	/// The claim was revoked.
+	Revoked,
+	/// The claim was transferred to `dest`.
+	Transferred,
+	/// The claim was imported from another registry.
+	Imported,
+}
+
+/// A lifecycle event of a claim.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimEvent {
+	/// The block the event was deposited in.
+	pub block_hash: Hash,
+	/// The number of that block.
+	pub block_number: BlockNumber,
+	/// What happened to the claim.
+	pub kind: ClaimEventKind,
+	/// The claim itself.
+	pub claim: Bytes,
+	/// The owner of the claim, or its previous owner when it was transferred.
+	pub owner: AccountId,
+	/// The new owner of a transferred claim.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub dest: Option<AccountId>,
+	/// When an imported claim was originally made, in milliseconds since the Unix epoch.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub timestamp: Option<u64>,
+}
+
+impl ClaimEvent {
+	/// The claim event of a runtime event deposited in block `block_hash`, if it is one.
+	pub fn from_event(block_hash: Hash, block_number: BlockNumber, event: Event) -> Option<Self> {
+		use pallet_template::Event as PoeEvent;
+
+		let (kind, claim, owner, dest, timestamp) = match event {
+			Event::TemplateModule(PoeEvent::ClaimCreated(owner, claim)) =>
+				(ClaimEventKind::Created, claim, owner, None, None),
+			Event::TemplateModule(PoeEvent::ClaimRevoked(owner, claim)) =>
+				(ClaimEventKind::Revoked, claim, owner, None, None),
+			Event::TemplateModule(PoeEvent::ClaimTransferred(owner, dest, claim)) =>
+				(ClaimEventKind::Transferred, claim, owner, Some(dest), None),
+			Event::TemplateModule(PoeEvent::ClaimImported(owner, claim, timestamp)) =>
+				(ClaimEventKind::Imported, claim, owner, None, Some(timestamp)),
+			_ => return None,
+		};
+		Some(ClaimEvent {
+			block_hash,
+			block_number,
+			kind,
+			claim: claim.into(),
+			owner,
+			dest,
+			timestamp,
+		})
+	}
+}
+
+/// A claim event as sent to subscribers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimEventNotification {
+	/// The event itself.
+	#[serde(flatten)]
+	pub event: ClaimEvent,
+	/// Whether the block of the event left the best chain, undoing the event. Only ever set for
+	/// subscriptions to best blocks.
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub retracted: bool,
+}
+
+/// Which claim events a subscriber is interested in. Every criterion left out matches anything.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ClaimEventFilter {
+	/// Only events of claims owned by, or transferred to, this account.
+	pub owner: Option<AccountId>,
+	/// Only events of claims starting with these bytes.
+	pub hash_prefix: Option<Bytes>,
+	/// Only events of these kinds.
+	pub kinds: Option<Vec<ClaimEventKind>>,
+	/// Only report events once their block is finalized, instead of when it becomes the best
+	/// block.
+	#[serde(default)]
+	pub finalized_only: bool,
+}
+
+impl ClaimEventFilter {
+	/// Whether `event` is of interest.
+	pub fn matches(&self, event: &ClaimEvent) -> bool {
+		self.owner
+			.as_ref()
+			.map_or(true, |owner| event.owner == *owner || event.dest.as_ref() == Some(owner))
+			&& self.hash_prefix.as_ref().map_or(true, |prefix| event.claim.starts_with(prefix))
+			&& self.kinds.as_ref().map_or(true, |kinds| kinds.contains(&event.kind))
+	}
+}
+
+/// Claim lifecycle events, pushed to subscribers as blocks are imported.
+#[rpc(server)]
+pub trait ClaimEventsApi {
+	/// RPC metadata.
+	type Metadata;
+
+	/// Subscribe to the claim events of new best blocks, or of finalized blocks with
+	/// `finalizedOnly`, optionally filtered by owner, claim prefix or kind. The events of best
+	/// blocks retracted by a re-organisation are sent again with `retracted` set.
+	#[pubsub(subscription = "poe_claimEvents", subscribe, name = "poe_subscribeClaimEvents")]
+	fn subscribe_claim_events(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<ClaimEventNotification>,
+		filter: Option<ClaimEventFilter>,
+	);
+
+	/// Unsubscribe from claim events.
+	#[pubsub(subscription = "poe_claimEvents", unsubscribe, name = "poe_unsubscribeClaimEvents")]
+	fn unsubscribe_claim_events(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool>;
+}
+
+/// A struct that implements the [`ClaimEventsApi`].
+pub struct ClaimEvents<C, B> {
+	client: Arc<C>,
+	subscriptions: SubscriptionManager,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> ClaimEvents<C, B> {
+	/// Create new `ClaimEvents` serving subscriptions with the given client.
+	pub fn new(client: Arc<C>, subscriptions: SubscriptionManager) -> Self {
+		Self { client, subscriptions, _marker: Default::default() }
+	}
+}
+
+/// The successive heads of the chain followed by a subscription.
+type Heads = Pin<Box<dyn Stream<Item = (Hash, BlockNumber)> + Send>>;
+
+/// A block to report: its hash, its number and whether it was retracted.
+type Step = (Hash, BlockNumber, bool);
+
+/// The blocks to report when the head moves from `from` to `to`: the retracted blocks, newest
+/// first, then the enacted blocks, oldest first, up to `to`.
+fn route<C>(client: &C, from: Hash, (to, number): (Hash, BlockNumber)) -> Vec<Step>
+where
+	C: HeaderMetadata<Block, Error = sp_blockchain::Error>,
+{
+	match sp_blockchain::tree_route(client, from, to) {
+		Ok(route) => route
+			.retracted()
+			.iter()
+			.map(|block| (block.hash, block.number, true))
+			.chain(route.enacted().iter().map(|block| (block.hash, block.number, false)))
+			.collect(),
+		Err(e) => {
+			log::warn!("Finding the route from {} to {} failed: {}", from, to, e);
+			vec![(to, number, false)]
+		},
+	}
+}
+
+impl<C, B> ClaimEventsApi for ClaimEvents<C, B>
+where
+	C: BlockchainEvents<Block> + StorageProvider<Block, B> + HeaderBackend<Block>,
+	C: HeaderMetadata<Block, Error = sp_blockchain::Error> + Send + Sync + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe_claim_events(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<ClaimEventNotification>,
+		filter: Option<ClaimEventFilter>,
+	) {
+		let filter = filter.unwrap_or_default();
+		// The streams are opened before the current head is read, so no block is missed.
+		let (heads, head): (Heads, Hash) = if filter.finalized_only {
+			let heads = self
+				.client
+				.finality_notification_stream()
+				.map(|notification| (notification.hash, *notification.header.number()));
+			(Box::pin(heads), self.client.info().finalized_hash)
+		} else {
+			// Forks are not reported, only the blocks joining or leaving the best chain.
+			let heads = self
+				.client
+				.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+				.map(|notification| (notification.hash, *notification.header.number()));
+			(Box::pin(heads), self.client.info().best_hash)
+		};
+
+		let client = self.client.clone();
+		let blocks = heads
+			.scan(head, move |head, new_head| {
+				let steps = route(&*client, *head, new_head);
+				*head = new_head.0;
+				future::ready(Some(stream::iter(steps)))
+			})
+			.flatten();
+
+		let client = self.client.clone();
+		self.subscriptions.add(subscriber, move |sink| {
+			blocks
+				.flat_map(move |(hash, number, retracted)| {
+					let mut events = block_events(&*client, hash).unwrap_or_else(|e| {
+						log::warn!("Reading events of {} for subscribers failed: {}", hash, e);
+						Vec::new()
+					});
+					// Retracted blocks are undone, so their events come newest first.
+					if retracted {
+						events.reverse();
+					}
+					let claim_events: Vec<_> = events
+						.into_iter()
+						.filter_map(|event| ClaimEvent::from_event(hash, number, event))
+						.filter(|event| filter.matches(event))
+						.map(|event| ClaimEventNotification { event, retracted })
+						.collect();
+					stream::iter(claim_events)
+				})
+				.map(|event| Ok::<_, ()>(Ok(event)))
+				.forward(sink.sink_map_err(|e| log::warn!("Error sending claim events: {:?}", e)))
+				.map(drop)
+		});
+	}
+
+	fn unsubscribe_claim_events(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::Runtime;
+	use pallet_template::Event as PoeEvent;
+	use sp_core::crypto::AccountId32;
+
+	fn account(seed: u8) -> AccountId {
+		AccountId32::new([seed; 32])
+	}
+
+	fn claim_event(event: PoeEvent<Runtime>) -> ClaimEvent {
+		ClaimEvent::from_event(Hash::repeat_byte(7), 42, Event::TemplateModule(event)).unwrap()
+	}
+
+	#[test]
+	fn decodes_claim_events() {
+		let event =
+			claim_event(PoeEvent::ClaimTransferred(account(1), account(2), b"claim".to_vec()));
+		assert_eq!(event.kind, ClaimEventKind::Transferred);
+		assert_eq!(event.claim, Bytes(b"claim".to_vec()));
+		assert_eq!((event.owner, event.dest), (account(1), Some(account(2))));
+
+		let event = claim_event(PoeEvent::ClaimImported(account(1), b"claim".to_vec(), 5));
+		assert_eq!((event.kind, event.timestamp), (ClaimEventKind::Imported, Some(5)));
+
+		let event = Event::System(frame_system::Event::CodeUpdated);
+		assert_eq!(ClaimEvent::from_event(Hash::zero(), 0, event), None);
+	}
+
+	#[test]
+	fn serializes_claim_events() {
+		let json =
+			serde_json::to_value(claim_event(PoeEvent::ClaimCreated(account(1), vec![1, 2])))
+				.unwrap();
+
+		assert_eq!(json["blockNumber"], 42);
+		assert_eq!(json["kind"], "created");
+		assert_eq!(json["claim"], "0x0102");
+		assert_eq!(json["owner"], serde_json::to_value(account(1)).unwrap());
+		assert!(json.get("dest").is_none());
+		assert!(json.get("timestamp").is_none());
+	}
+
+	#[test]
+	fn serializes_retracted_claim_events() {
+		let event = claim_event(PoeEvent::ClaimCreated(account(1), vec![1, 2]));
+		let enacted = ClaimEventNotification { event: event.clone(), retracted: false };
+		let retracted = ClaimEventNotification { event, retracted: true };
+
+		let json = serde_json::to_value(enacted).unwrap();
+		assert_eq!(json["kind"], "created");
+		assert!(json.get("retracted").is_none());
+
+		let json = serde_json::to_value(retracted).unwrap();
+		assert_eq!(json["kind"], "created");
+		assert_eq!(json["retracted"], true);
+	}
+
+	#[test]
+	fn filters_claim_events() {
+		let filter: ClaimEventFilter = serde_json::from_value(serde_json::json!({
+			"owner": account(2),
+			"hashPrefix": "0x0102",
+			"kinds": ["created", "transferred"],
+		}))
+		.unwrap();
+		assert!(!filter.finalized_only);
+
+		let created = claim_event(PoeEvent::ClaimCreated(account(2), vec![1, 2, 3]));
+		let transferred =
+			claim_event(PoeEvent::ClaimTransferred(account(1), account(2), vec![1, 2]));
+		assert!(filter.matches(&created));
+		assert!(filter.matches(&transferred));
+
+		assert!(!filter.matches(&claim_event(PoeEvent::ClaimCreated(account(3), vec![1, 2]))));
+		assert!(!filter.matches(&claim_event(PoeEvent::ClaimCreated(account(2), vec![1, 3]))));
+		assert!(!filter.matches(&claim_event(PoeEvent::ClaimRevoked(account(2), vec![1, 2]))));
+		assert!(ClaimEventFilter::default().matches(&created));
+	}
+
+	#[test]
+	fn rejects_unknown_filters() {
+		let filter = serde_json::from_value::<ClaimEventFilter>(serde_json::json!({ "who": 1 }));
+		assert!(filter.is_err());
+	}
+}
@@ -0,0 +1,158 @@
+//! Claim verification on light clients, which hold headers but no state.
+//!
+//! The `Proofs` entry of a claim is fetched from full nodes with a storage read proof, which the
+//! on-demand fetcher checks against the state root of the last header finalized by GRANDPA.
+
+use std::{collections::HashMap, sync::Arc};
+
+use codec::Decode;
+use futures::future;
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash, TemplateModule};
+use sc_client_api::light::{Fetcher, RemoteReadRequest};
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Header as _};
+
+/// The answer of a light client to "does this hash exist and who owns it".
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LightVerification {
+	/// The claimed hash.
+	pub hash: Bytes,
+	/// Owner of the claim and the block it was claimed at, `None` if it was not claimed.
+	pub claim: Option<(AccountId, BlockNumber)>,
+	/// The finalized block the claim was proven at.
+	pub block_hash: Hash,
+	/// The number of that block.
+	pub block_number: BlockNumber,
+}
+
+/// Claim verification against finalized headers.
+#[rpc(server)]
+pub trait LightVerifyApi {
+	/// Prove the claim of `hash`, if any, as of the last finalized block known to this node.
+	#[rpc(name = "poe_verifyLight")]
+	fn verify_light(&self, hash: Bytes) -> BoxFuture<Result<LightVerification>>;
+}
+
+/// A struct that implements the [`LightVerifyApi`].
+pub struct LightVerify<C, F> {
+	client: Arc<C>,
+	fetcher: Arc<F>,
+}
+
+impl<C, F> LightVerify<C, F> {
+	/// Create new `LightVerify` reading headers from `client` and proofs through `fetcher`.
+	pub fn new(client: Arc<C>, fetcher: Arc<F>) -> Self {
+		Self { client, fetcher }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The finalized header could not be read.
+	BlockchainError,
+	/// The storage proof could not be fetched or did not check out.
+	RemoteReadError,
+	/// The proven value is not a claim.
+	InvalidClaim,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::BlockchainError => 1,
+			Error::RemoteReadError => 2,
+			Error::InvalidClaim => 3,
+		}
+	}
+}
+
+fn rpc_error(error: Error, message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(error.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+/// The claim stored at `key` among proven `values`.
+fn proven_claim(
+	values: &mut HashMap<Vec<u8>, Option<Vec<u8>>>,
+	key: &[u8],
+) -> std::result::Result<Option<(AccountId, BlockNumber)>, codec::Error> {
+	values
+		.remove(key)
+		.flatten()
+		.map(|raw| <(AccountId, BlockNumber)>::decode(&mut &raw[..]))
+		.transpose()
+}
+
+impl<C, F> LightVerifyApi for LightVerify<C, F>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + Send + Sync + 'static,
+	F::RemoteReadResult: Send + 'static,
+{
+	fn verify_light(&self, hash: Bytes) -> BoxFuture<Result<LightVerification>> {
+		let block_hash = self.client.info().finalized_hash;
+		let header = self
+			.client
+			.header(BlockId::Hash(block_hash))
+			.map_err(|e| rpc_error(Error::BlockchainError, "Unable to read block.", e))
+			.and_then(|header| {
+				header.ok_or_else(|| {
+					let e = format!("Unknown block {}", block_hash);
+					rpc_error(Error::BlockchainError, "Unable to read block.", e)
+				})
+			});
+		let header = match header {
+			Ok(header) => header,
+			Err(e) => return Box::pin(future::err(e)),
+		};
+		let block_number = *header.number();
+		let key = TemplateModule::proof_key(&hash);
+		let read = self.fetcher.remote_read(RemoteReadRequest {
+			block: block_hash,
+			header,
+			keys: vec![key.clone()],
+			retry_count: None,
+		});
+
+		Box::pin(async move {
+			let mut values = read
+				.await
+				.map_err(|e| rpc_error(Error::RemoteReadError, "Unable to prove claim.", e))?;
+			let claim = proven_claim(&mut values, &key)
+				.map_err(|e| rpc_error(Error::InvalidClaim, "Unable to decode claim.", e))?;
+			Ok(LightVerification { hash, claim, block_hash, block_number })
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_core::crypto::AccountId32;
+
+	#[test]
+	fn decodes_proven_claims() {
+		let owner = AccountId32::new([1; 32]);
+		let mut values: HashMap<_, _> = vec![
+			(b"claimed".to_vec(), Some((owner.clone(), 7u32).encode())),
+			(b"unclaimed".to_vec(), None),
+			(b"invalid".to_vec(), Some(vec![1, 2, 3])),
+		]
+		.into_iter()
+		.collect();
+
+		assert_eq!(proven_claim(&mut values, b"claimed").unwrap(), Some((owner, 7)));
+		assert_eq!(proven_claim(&mut values, b"unclaimed").unwrap(), None);
+		assert_eq!(proven_claim(&mut values, b"missing").unwrap(), None);
+		assert!(proven_claim(&mut values, b"invalid").is_err());
+	}
+}
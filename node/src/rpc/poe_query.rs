@@ -0,0 +1,50 @@
+//! Queries over the SQLite index of claim events kept with `--poe-index-db`.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+
+use crate::indexer::{EventPage, EventQuery, IndexStatus, PoeIndex};
+
+/// Claim events recorded in the node's index.
+#[rpc(server)]
+pub trait PoeQueryApi {
+	/// A page of indexed events matching `query`, oldest first.
+	#[rpc(name = "poe_queryEvents")]
+	fn events(&self, query: Option<EventQuery>) -> Result<EventPage>;
+
+	/// How far the index goes.
+	#[rpc(name = "poe_queryStatus")]
+	fn status(&self) -> Result<IndexStatus>;
+}
+
+/// A struct that implements the [`PoeQueryApi`].
+pub struct PoeQuery {
+	index: Arc<PoeIndex>,
+}
+
+impl PoeQuery {
+	/// Create new `PoeQuery` reading from the given index.
+	pub fn new(index: Arc<PoeIndex>) -> Self {
+		Self { index }
+	}
+}
+
+fn index_error(e: rusqlite::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to query the claim index.".into(),
+		data: Some(e.to_string().into()),
+	}
+}
+
+impl PoeQueryApi for PoeQuery {
+	fn events(&self, query: Option<EventQuery>) -> Result<EventPage> {
+		self.index.query(&query.unwrap_or_default()).map_err(index_error)
+	}
+
+	fn status(&self) -> Result<IndexStatus> {
+		self.index.status().map_err(index_error)
+	}
+}
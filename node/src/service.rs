@@ -1,17 +1,34 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
+use crate::{
+	cli::Sealing, indexer::PoeIndex, metrics::PoeMetrics, remote_keystore::RemoteKeystore,
+};
+use futures::{stream, FutureExt, Stream, StreamExt};
+use node_template_runtime::{self, opaque::Block, Hash, RuntimeApi};
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+use sc_consensus_manual_seal::{
+	consensus::ConsensusDataProvider, EngineCommand, Error as ManualSealError, ManualSealParams,
+};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sc_finality_grandpa::SharedVoterState;
-use sc_keystore::LocalKeystore;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sp_blockchain::HeaderBackend;
 use sp_consensus::SlotData;
-use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-use std::{sync::Arc, time::Duration};
+use sp_consensus_aura::{
+	digests::CompatibleDigestItem,
+	sr25519::{AuthorityPair as AuraPair, AuthoritySignature as AuraSignature},
+	Slot,
+};
+use sp_inherents::InherentData;
+use sp_runtime::{
+	generic::{BlockId, Digest},
+	traits::{Block as BlockT, DigestFor, DigestItemFor},
+};
+use std::{path::PathBuf, pin::Pin, sync::Arc, time::Duration};
+use substrate_prometheus_endpoint::Registry;
 
 // Our native executor instance.
 native_executor_instance!(
@@ -47,10 +64,6 @@ pub fn new_partial(
 	>,
 	ServiceError,
 > {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other(format!("Remote Keystores are not supported.")))
-	}
-
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -130,15 +143,63 @@ pub fn new_partial(
 	})
 }
 
-fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
-	// FIXME: here would the concrete keystore be built,
-	//        must return a concrete type (NOT `LocalKeystore`) that
-	//        implements `CryptoStore` and `SyncCryptoStore`
-	Err("Remote Keystore not supported.")
+/// Spawns the task keeping the proof-of-existence metrics up to date, if Prometheus is enabled.
+fn spawn_poe_metrics(
+	task_manager: &TaskManager,
+	client: Arc<FullClient>,
+	registry: Option<&Registry>,
+) -> Result<(), ServiceError> {
+	if let Some(registry) = registry {
+		let metrics = PoeMetrics::register(registry)?;
+		// Counting the claims reads storage synchronously, so it gets a thread of its own.
+		task_manager
+			.spawn_handle()
+			.spawn_blocking("poe-metrics", crate::metrics::run(client, metrics));
+	}
+	Ok(())
+}
+
+/// Opens the claim index at `path`, if any, and spawns the task keeping it up to date.
+///
+/// The index is built from the state of every block since genesis, so it needs an archive node.
+fn spawn_poe_index(
+	task_manager: &TaskManager,
+	config: &Configuration,
+	client: Arc<FullClient>,
+	path: Option<PathBuf>,
+) -> Result<Option<Arc<PoeIndex>>, ServiceError> {
+	let path = match path {
+		Some(path) => path,
+		None => return Ok(None),
+	};
+	if !config.state_pruning.is_archive() {
+		return Err(ServiceError::Other(
+			"The claim index needs the state of every block: run with `--pruning archive`".into(),
+		))
+	}
+	let index = PoeIndex::open(&path).map_err(|e| {
+		ServiceError::Other(format!("Error opening the claim index at {}: {}", path.display(), e))
+	})?;
+	let index = Arc::new(index);
+	task_manager
+		.spawn_handle()
+		.spawn_blocking("poe-index", crate::indexer::run(client, index.clone()));
+	Ok(Some(index))
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+///
+/// With `sealing` set, blocks are produced by manual seal instead of Aura and GRANDPA. With
+/// `poe_index_db` set, claim events are indexed into an SQLite database at that path.
+pub fn new_full(
+	mut config: Configuration,
+	sealing: Option<Sealing>,
+	poe_index_db: Option<PathBuf>,
+) -> Result<TaskManager, ServiceError> {
+	if let Some(sealing) = sealing {
+		return new_manual_seal(config, sealing, poe_index_db)
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -151,8 +212,8 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	} = new_partial(&config)?;
 
 	if let Some(url) = &config.keystore_remote {
-		match remote_keystore(url) {
-			Ok(k) => keystore_container.set_remote_keystore(k),
+		match RemoteKeystore::open(url) {
+			Ok(k) => keystore_container.set_remote_keystore(Arc::new(k)),
 			Err(e) =>
 				return Err(ServiceError::Other(format!(
 					"Error hooking up remote keystore for {}: {}",
@@ -189,14 +250,22 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
+	spawn_poe_metrics(&task_manager, client.clone(), prometheus_registry.as_ref())?;
+	let poe_index = spawn_poe_index(&task_manager, &config, client.clone(), poe_index_db)?;
 
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				command_sink: None,
+				subscription_executor,
+				poe_index: poe_index.clone(),
+			};
 
 			crate::rpc::create_full(deps)
 		})
@@ -312,6 +381,224 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	Ok(task_manager)
 }
 
+/// Puts an Aura pre-runtime digest for the slot in the inherent data into manually sealed
+/// blocks, so that the runtime's Aura and timestamp checks keep passing.
+struct AuraSlotDigestProvider;
+
+impl ConsensusDataProvider<Block> for AuraSlotDigestProvider {
+	type Transaction = sp_api::TransactionFor<FullClient, Block>;
+
+	fn create_digest(
+		&self,
+		_parent: &<Block as BlockT>::Header,
+		inherents: &InherentData,
+	) -> Result<DigestFor<Block>, ManualSealError> {
+		let slot = inherents
+			.get_data::<Slot>(&sp_consensus_aura::inherents::INHERENT_IDENTIFIER)
+			.map_err(|e| ManualSealError::StringError(format!("{:?}", e)))?
+			.ok_or_else(|| ManualSealError::StringError("No Aura slot in inherent data".into()))?;
+
+		let pre_digest =
+			<DigestItemFor<Block> as CompatibleDigestItem<AuraSignature>>::aura_pre_digest(slot);
+
+		Ok(Digest { logs: vec![pre_digest] })
+	}
+
+	fn append_block_import(
+		&self,
+		_parent: &<Block as BlockT>::Header,
+		_params: &mut sc_consensus::BlockImportParams<Block, Self::Transaction>,
+		_inherents: &InherentData,
+	) -> Result<(), ManualSealError> {
+		Ok(())
+	}
+}
+
+type EngineCommands = Pin<Box<dyn Stream<Item = EngineCommand<Hash>> + Send>>;
+
+/// Seal (and finalize) a new block, possibly empty, on top of the best block.
+fn seal_command(create_empty: bool) -> EngineCommand<Hash> {
+	EngineCommand::SealNewBlock { create_empty, finalize: true, parent_hash: None, sender: None }
+}
+
+/// Builds a new service for a full client producing blocks with manual seal.
+fn new_manual_seal(
+	config: Configuration,
+	sealing: Sealing,
+	poe_index_db: Option<PathBuf>,
+) -> Result<TaskManager, ServiceError> {
+	let telemetry = config
+		.telemetry_endpoints
+		.clone()
+		.filter(|x| !x.is_empty())
+		.map(|endpoints| -> Result<_, sc_telemetry::Error> {
+			let worker = TelemetryWorker::new(16)?;
+			let telemetry = worker.handle().new_telemetry(endpoints);
+			Ok((worker, telemetry))
+		})
+		.transpose()?;
+
+	let (client, backend, keystore_container, mut task_manager) =
+		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(
+			&config,
+			telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
+		)?;
+	let client = Arc::new(client);
+
+	let mut telemetry = telemetry.map(|(worker, telemetry)| {
+		task_manager.spawn_handle().spawn("telemetry", worker.run());
+		telemetry
+	});
+
+	let select_chain = sc_consensus::LongestChain::new(backend.clone());
+
+	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
+		config.transaction_pool.clone(),
+		config.role.is_authority().into(),
+		config.prometheus_registry(),
+		task_manager.spawn_essential_handle(),
+		client.clone(),
+	);
+
+	let import_queue = sc_consensus_manual_seal::import_queue(
+		Box::new(client.clone()),
+		&task_manager.spawn_essential_handle(),
+		config.prometheus_registry(),
+	);
+
+	let (network, system_rpc_tx, network_starter) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			on_demand: None,
+			block_announce_validator_builder: None,
+		})?;
+
+	if config.offchain_worker.enabled {
+		sc_service::build_offchain_workers(
+			&config,
+			task_manager.spawn_handle(),
+			client.clone(),
+			network.clone(),
+		);
+	}
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+	spawn_poe_metrics(&task_manager, client.clone(), prometheus_registry.as_ref())?;
+	let poe_index = spawn_poe_index(&task_manager, &config, client.clone(), poe_index_db)?;
+	let (command_sink, rpc_commands) = futures::channel::mpsc::channel(1024);
+
+	let rpc_extensions_builder = {
+		let client = client.clone();
+		let pool = transaction_pool.clone();
+
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				command_sink: Some(command_sink.clone()),
+				subscription_executor,
+				poe_index: poe_index.clone(),
+			};
+
+			crate::rpc::create_full(deps)
+		})
+	};
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		network,
+		client: client.clone(),
+		keystore: keystore_container.sync_keystore(),
+		task_manager: &mut task_manager,
+		transaction_pool: transaction_pool.clone(),
+		rpc_extensions_builder,
+		on_demand: None,
+		remote_blockchain: None,
+		backend,
+		system_rpc_tx,
+		config,
+		telemetry: telemetry.as_mut(),
+	})?;
+
+	// Blocks requested over RPC are always honoured, the sealing mode only adds its own.
+	let commands_stream: EngineCommands = match sealing {
+		Sealing::Manual => Box::pin(rpc_commands),
+		Sealing::Instant => Box::pin(stream::select(
+			rpc_commands,
+			transaction_pool
+				.pool()
+				.validated_pool()
+				.import_notification_stream()
+				.map(|_| seal_command(false)),
+		)),
+		Sealing::Interval(millis) => Box::pin(stream::select(
+			rpc_commands,
+			stream::unfold((), move |()| {
+				futures_timer::Delay::new(Duration::from_millis(millis))
+					.map(|()| Some((seal_command(true), ())))
+			}),
+		)),
+	};
+
+	let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+		telemetry.as_ref().map(|x| x.handle()),
+	);
+
+	// Every sealed block moves on by at least one slot, so the timestamp always advances by a
+	// full slot duration no matter how fast blocks are requested.
+	let slot_duration = sc_consensus_aura::slot_duration(&*client)?.slot_duration();
+	let create_inherent_data_providers = {
+		let client = client.clone();
+		move |parent, ()| {
+			let parent_slot = client
+				.header(BlockId::Hash(parent))
+				.ok()
+				.flatten()
+				.and_then(|header| {
+					sc_consensus_aura::find_pre_digest::<Block, AuraSignature>(&header).ok()
+				})
+				.map_or(0, u64::from);
+
+			async move {
+				let now = u64::from(*sp_timestamp::InherentDataProvider::from_system_time());
+				let slot = std::cmp::max(parent_slot + 1, now / slot_duration);
+
+				let timestamp =
+					sp_timestamp::InherentDataProvider::new((slot * slot_duration).into());
+				let slot = sp_consensus_aura::inherents::InherentDataProvider::new(slot.into());
+
+				Ok((timestamp, slot))
+			}
+		}
+	};
+
+	let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+		block_import: client.clone(),
+		env: proposer_factory,
+		client,
+		pool: transaction_pool.pool().clone(),
+		commands_stream,
+		select_chain,
+		consensus_data_provider: Some(Box::new(AuraSlotDigestProvider)),
+		create_inherent_data_providers,
+	});
+
+	// the manual seal authoring task is considered essential, i.e. if it
+	// fails we take down the service with it.
+	task_manager.spawn_essential_handle().spawn_blocking("manual-seal", manual_seal);
+
+	network_starter.start_network();
+	Ok(task_manager)
+}
+
 /// Builds a new service for a light client.
 pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError> {
 	let telemetry = config
@@ -420,12 +707,22 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 	}
 
+	let rpc_extensions_builder = {
+		let client = client.clone();
+
+		Box::new(move |_, _| {
+			let deps = crate::rpc::LightDeps { client: client.clone(), fetcher: on_demand.clone() };
+
+			crate::rpc::create_light(deps)
+		})
+	};
+
 	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		remote_blockchain: Some(backend.remote_blockchain()),
 		transaction_pool,
 		task_manager: &mut task_manager,
-		on_demand: Some(on_demand),
-		rpc_extensions_builder: Box::new(|_, _| ()),
+		on_demand: Some(on_demand.clone()),
+		rpc_extensions_builder,
 		config,
 		client,
 		keystore: keystore_container.sync_keystore(),
@@ -0,0 +1,225 @@
+//! Random sequences of `create_claim`, `revoke_claim` and `transfer_clain` from random origins,
+//! against the mock runtime of the unit tests.
+//!
+//! Every call is checked against a model of the registry: it must succeed or fail exactly as the
+//! model says, every claim must have the owner the model gives it, and the claims in storage must
+//! add up with the events deposited so far.
+//!
+//! Run from `pallets/template` with `cargo +nightly fuzz run claims`.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use frame_support::dispatch::DispatchResultWithPostInfo;
+use frame_system::RawOrigin;
+use libfuzzer_sys::fuzz_target;
+use pallet_template::{
+	mock::{new_test_ext, run_to_block, Event, Origin, System, TemplateModule, Test},
+	Error, Event as PoeEvent,
+};
+use sp_runtime::{DispatchError, DispatchResult};
+use std::collections::BTreeMap;
+
+/// Calls are made by accounts `0..ACCOUNTS`. `0` is also the default `AccountId`, so calls from
+/// it check that missing claims are not taken for claims of the default account.
+const ACCOUNTS: u8 = 4;
+
+/// The length of each claim in the pool: the bounds of `MinPoeLength` and `MaxPoeLength`, and
+/// one byte beyond each. Claims are drawn from a small pool so that calls keep hitting the same
+/// claims.
+const CLAIM_LENGTHS: [usize; 8] = [7, 8, 8, 16, 20, 32, 32, 33];
+
+#[derive(Debug, Arbitrary)]
+enum Caller {
+	Signed(u8),
+	Root,
+	None,
+}
+
+#[derive(Debug, Arbitrary)]
+enum Action {
+	Create { caller: Caller, claim: u8 },
+	Revoke { caller: Caller, claim: u8 },
+	Transfer { caller: Caller, claim: u8, dest: u8 },
+	NextBlock,
+}
+
+fn account(index: u8) -> u64 {
+	u64::from(index % ACCOUNTS)
+}
+
+fn claim(index: u8) -> Vec<u8> {
+	let index = index as usize % CLAIM_LENGTHS.len();
+	vec![index as u8; CLAIM_LENGTHS[index]]
+}
+
+fn pool() -> impl Iterator<Item = Vec<u8>> {
+	(0..CLAIM_LENGTHS.len() as u8).map(claim)
+}
+
+/// The owner of every claim, as the pallet should have it.
+#[derive(Default)]
+struct Model(BTreeMap<Vec<u8>, u64>);
+
+impl Model {
+	/// The signer of a call from `caller`, or the error of any call from it.
+	fn signer(caller: &Caller) -> Result<u64, DispatchError> {
+		match caller {
+			Caller::Signed(index) => Ok(account(*index)),
+			Caller::Root | Caller::None => Err(DispatchError::BadOrigin),
+		}
+	}
+
+	fn check_length(claim: &[u8]) -> DispatchResult {
+		if claim.len() < TemplateModule::min_length() as usize {
+			return Err(Error::<Test>::TooShort.into())
+		}
+		if claim.len() > TemplateModule::max_length() as usize {
+			return Err(Error::<Test>::TooLong.into())
+		}
+		Ok(())
+	}
+
+	/// The claim's owner, if `caller` owns it.
+	fn check_owner(&self, caller: &Caller, claim: &[u8]) -> Result<u64, DispatchError> {
+		let signer = Self::signer(caller)?;
+		Self::check_length(claim)?;
+		match self.0.get(claim) {
+			None => Err(Error::<Test>::NoSuchProof.into()),
+			Some(owner) if *owner != signer => Err(Error::<Test>::NotProofOwner.into()),
+			Some(_) => Ok(signer),
+		}
+	}
+
+	fn create(&mut self, caller: &Caller, claim: &[u8]) -> DispatchResult {
+		let signer = Self::signer(caller)?;
+		Self::check_length(claim)?;
+		if self.0.contains_key(claim) {
+			return Err(Error::<Test>::ProofAlreadyClaimed.into())
+		}
+		self.0.insert(claim.to_vec(), signer);
+		Ok(())
+	}
+
+	fn revoke(&mut self, caller: &Caller, claim: &[u8]) -> DispatchResult {
+		self.check_owner(caller, claim)?;
+		self.0.remove(claim);
+		Ok(())
+	}
+
+	fn transfer(&mut self, caller: &Caller, claim: &[u8], dest: u64) -> DispatchResult {
+		self.check_owner(caller, claim)?;
+		self.0.insert(claim.to_vec(), dest);
+		Ok(())
+	}
+
+	/// The number of claims held by every owner.
+	fn holdings(&self) -> BTreeMap<u64, usize> {
+		let mut holdings = BTreeMap::new();
+		for owner in self.0.values() {
+			*holdings.entry(*owner).or_default() += 1;
+		}
+		holdings
+	}
+}
+
+fn origin(caller: &Caller) -> Origin {
+	match caller {
+		Caller::Signed(index) => RawOrigin::Signed(account(*index)).into(),
+		Caller::Root => RawOrigin::Root.into(),
+		Caller::None => RawOrigin::None.into(),
+	}
+}
+
+fn outcome(result: DispatchResultWithPostInfo) -> DispatchResult {
+	result.map(drop).map_err(|e| e.error)
+}
+
+/// The number of entries in `Proofs`.
+fn stored_claims() -> usize {
+	let prefix = TemplateModule::proofs_prefix();
+	let mut key = prefix.to_vec();
+	let mut count = 0;
+	while let Some(next) = sp_io::storage::next_key(&key) {
+		if !next.starts_with(&prefix) {
+			break
+		}
+		count += 1;
+		key = next;
+	}
+	count
+}
+
+/// The number of claims held by every owner, replayed from the events deposited so far.
+fn holdings_from_events() -> BTreeMap<u64, usize> {
+	let mut holdings = BTreeMap::<u64, usize>::new();
+	for record in System::events() {
+		match record.event {
+			Event::TemplateModule(PoeEvent::ClaimCreated(owner, _)) =>
+				*holdings.entry(owner).or_default() += 1,
+			Event::TemplateModule(PoeEvent::ClaimRevoked(owner, _)) =>
+				*holdings.get_mut(&owner).expect("revoked claims were created") -= 1,
+			Event::TemplateModule(PoeEvent::ClaimTransferred(from, to, _)) => {
+				*holdings.get_mut(&from).expect("transferred claims were created") -= 1;
+				*holdings.entry(to).or_default() += 1;
+			},
+			_ => {},
+		}
+	}
+	holdings.retain(|_, held| *held > 0);
+	holdings
+}
+
+fn check_invariants(model: &Model) {
+	// Every claim has exactly the owner the model gives it, and no other claim exists.
+	for claim in pool() {
+		assert_eq!(
+			TemplateModule::proof_of(&claim).map(|(owner, _)| owner),
+			model.0.get(&claim).copied(),
+			"owner of {:?}",
+			claim
+		);
+	}
+	assert_eq!(stored_claims(), model.0.len(), "claims in storage");
+
+	// The claims of every owner add up to the claims in storage, and to the events.
+	let holdings = model.holdings();
+	assert_eq!(holdings.values().sum::<usize>(), stored_claims(), "claims held");
+	assert_eq!(holdings_from_events(), holdings, "claims held according to events");
+}
+
+fuzz_target!(|actions: Vec<Action>| {
+	new_test_ext().execute_with(|| {
+		// Events are not recorded at genesis.
+		run_to_block(1);
+		let mut model = Model::default();
+
+		for action in actions {
+			let (actual, expected) = match &action {
+				Action::Create { caller, claim: index } => {
+					let claim = claim(*index);
+					let actual = TemplateModule::create_claim(origin(caller), claim.clone());
+					(outcome(actual), model.create(caller, &claim))
+				},
+				Action::Revoke { caller, claim: index } => {
+					let claim = claim(*index);
+					let actual = TemplateModule::revoke_claim(origin(caller), claim.clone());
+					(outcome(actual), model.revoke(caller, &claim))
+				},
+				Action::Transfer { caller, claim: index, dest } => {
+					let claim = claim(*index);
+					let dest = account(*dest);
+					let actual =
+						TemplateModule::transfer_clain(origin(caller), claim.clone(), dest);
+					(outcome(actual), model.transfer(caller, &claim, dest))
+				},
+				Action::NextBlock => {
+					run_to_block(System::block_number() + 1);
+					(Ok(()), Ok(()))
+				},
+			};
+			assert_eq!(actual, expected, "outcome of {:?}", action);
+			check_invariants(&model);
+		}
+	})
+});
@@ -0,0 +1,22 @@
+//! Runtime API definition for the proof-of-existence pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	pub trait PoeApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The owner of `claim` and the block it was claimed at, if it has been claimed.
+		fn proof_of(claim: Vec<u8>) -> Option<(AccountId, BlockNumber)>;
+
+		/// The raw storage key of `claim` in the `Proofs` map.
+		fn proof_key(claim: Vec<u8>) -> Vec<u8>;
+
+		/// The display name of the owner of `claim`, if a registrar has verified their identity.
+		fn owner_identity(claim: Vec<u8>) -> Option<Vec<u8>>;
+	}
+}
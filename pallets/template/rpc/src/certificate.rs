@@ -0,0 +1,125 @@
+//! Self-contained proof that a claim existed (or did not exist) at a given block.
+
+use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sp_runtime::traits::Header as HeaderT;
+use sp_state_machine::{read_proof_check, StorageProof};
+use std::fmt;
+
+/// A portable certificate for a single claim.
+///
+/// It bundles the claim as read from the runtime, the header of the block it was read at and a
+/// storage read proof of the `Proofs` entry, so it can be checked offline against nothing but a
+/// trusted block hash.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoeCertificate<AccountId, BlockNumber, Header: HeaderT> {
+	/// The claimed hash.
+	#[serde(with = "sp_core::bytes")]
+	pub hash: Vec<u8>,
+	/// Owner of the claim and the block it was claimed at, `None` if it was not claimed.
+	pub claim: Option<(AccountId, BlockNumber)>,
+	/// Verified display name of the claim owner, if any.
+	///
+	/// This is informational only: it is not covered by `proof` and not checked by `verify`.
+	pub owner_identity: Option<String>,
+	/// Hash of the block the certificate was produced at.
+	pub block_hash: Header::Hash,
+	/// Header of the block the certificate was produced at.
+	pub header: Header,
+	/// Raw storage key of the claim in `Proofs`.
+	#[serde(with = "sp_core::bytes")]
+	pub key: Vec<u8>,
+	/// Trie nodes proving the value (or absence) of `key` under the header's state root.
+	#[serde(with = "proof_nodes")]
+	pub proof: Vec<Vec<u8>>,
+}
+
+/// Reasons a certificate can fail verification.
+#[derive(Debug, Eq, PartialEq)]
+pub enum VerifyError {
+	/// The header does not hash to the certified block hash.
+	HeaderMismatch,
+	/// The certified block is not the trusted one.
+	UntrustedBlock,
+	/// The certified key is not the storage key of the certified hash.
+	KeyMismatch,
+	/// The storage proof does not check out against the header's state root.
+	InvalidProof(String),
+	/// The proven storage value does not match the certified claim.
+	ClaimMismatch,
+}
+
+impl fmt::Display for VerifyError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			VerifyError::HeaderMismatch =>
+				write!(f, "Header does not match the certified block hash"),
+			VerifyError::UntrustedBlock => write!(f, "Certified block is not the trusted block"),
+			VerifyError::KeyMismatch => write!(f, "Storage key does not belong to the claim"),
+			VerifyError::InvalidProof(e) => write!(f, "Invalid storage proof: {}", e),
+			VerifyError::ClaimMismatch => write!(f, "Proven value does not match the claim"),
+		}
+	}
+}
+
+impl std::error::Error for VerifyError {}
+
+impl<AccountId, BlockNumber, Header> PoeCertificate<AccountId, BlockNumber, Header>
+where
+	AccountId: Decode + PartialEq,
+	BlockNumber: Decode + PartialEq,
+	Header: HeaderT,
+{
+	/// Check the certificate against the `trusted` block hash.
+	///
+	/// `expected_key` is the storage key of `self.hash` as computed by the verifier, so that a
+	/// certificate cannot prove some other entry.
+	pub fn verify(&self, trusted: &Header::Hash, expected_key: &[u8]) -> Result<(), VerifyError> {
+		let block_hash = self.header.hash();
+		if block_hash != self.block_hash {
+			return Err(VerifyError::HeaderMismatch)
+		}
+		if &block_hash != trusted {
+			return Err(VerifyError::UntrustedBlock)
+		}
+		if self.key != expected_key {
+			return Err(VerifyError::KeyMismatch)
+		}
+
+		let proof = StorageProof::new(self.proof.iter().cloned());
+		let mut values = read_proof_check::<Header::Hashing, _>(
+			*self.header.state_root(),
+			proof,
+			std::iter::once(&self.key),
+		)
+		.map_err(|e| VerifyError::InvalidProof(format!("{:?}", e)))?;
+
+		let proven = values
+			.remove(&self.key)
+			.flatten()
+			.map(|raw| <(AccountId, BlockNumber)>::decode(&mut &raw[..]))
+			.transpose()
+			.map_err(|_| VerifyError::ClaimMismatch)?;
+
+		if proven != self.claim {
+			return Err(VerifyError::ClaimMismatch)
+		}
+
+		Ok(())
+	}
+}
+
+/// Serialize trie nodes as a list of hex strings, matching `state_getReadProof`.
+mod proof_nodes {
+	use serde::{Deserialize, Deserializer, Serializer};
+	use sp_core::Bytes;
+
+	pub fn serialize<S: Serializer>(nodes: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(nodes.iter().cloned().map(Bytes))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+		Ok(Vec::<Bytes>::deserialize(deserializer)?.into_iter().map(|node| node.0).collect())
+	}
+}
@@ -0,0 +1,120 @@
+//! RPC interface for the proof-of-existence pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_client_api::ProofProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_template_rpc_runtime_api::PoeApi as PoeRuntimeApi;
+
+mod certificate;
+
+pub use certificate::{PoeCertificate, VerifyError};
+
+#[rpc]
+pub trait PoeApi<BlockHash, Certificate> {
+	/// Build a portable certificate for `hash` at block `at` (the last finalized block by default).
+	#[rpc(name = "poe_certificate")]
+	fn certificate(&self, hash: Bytes, at: Option<BlockHash>) -> Result<Certificate>;
+}
+
+/// A struct that implements the [`PoeApi`].
+pub struct Poe<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Poe<C, B> {
+	/// Create new `Poe` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The requested block could not be read.
+	BlockchainError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::BlockchainError => 2,
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to query claim.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn blockchain_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::BlockchainError.into()),
+		message: "Unable to read block.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber>
+	PoeApi<<Block as BlockT>::Hash, PoeCertificate<AccountId, BlockNumber, Block::Header>>
+	for Poe<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + ProofProvider<Block>,
+	C: Send + Sync + 'static,
+	C::Api: PoeRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec,
+	BlockNumber: Codec,
+{
+	fn certificate(
+		&self,
+		hash: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<PoeCertificate<AccountId, BlockNumber, Block::Header>> {
+		let block_hash = at.unwrap_or_else(|| self.client.info().finalized_hash);
+		let at = BlockId::hash(block_hash);
+
+		let api = self.client.runtime_api();
+		let claim = api.proof_of(&at, hash.to_vec()).map_err(runtime_error)?;
+		let key = api.proof_key(&at, hash.to_vec()).map_err(runtime_error)?;
+		let owner_identity = api
+			.owner_identity(&at, hash.to_vec())
+			.map_err(runtime_error)?
+			.map(|name| String::from_utf8_lossy(&name).into_owned());
+
+		let header = self
+			.client
+			.header(at)
+			.map_err(blockchain_error)?
+			.ok_or_else(|| blockchain_error(format!("Unknown block {}", block_hash)))?;
+		let proof = self
+			.client
+			.read_proof(&at, &mut std::iter::once(key.as_slice()))
+			.map_err(blockchain_error)?;
+
+		Ok(PoeCertificate {
+			hash: hash.to_vec(),
+			claim,
+			owner_identity,
+			block_hash,
+			header,
+			key,
+			proof: proof.iter_nodes().collect(),
+		})
+	}
+}
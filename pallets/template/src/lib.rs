@@ -5,8 +5,8 @@
 /// <https://substrate.dev/docs/en/knowledgebase/runtime/frame>
 pub use pallet::*;
 
-#[cfg(test)]
-mod mock;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod mock;
 
 #[cfg(test)]
 mod tests;
@@ -14,25 +14,92 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+/// Unlocks the funds of an account that have vested but are still locked, e.g. by calling
+/// `pallet_vesting`'s `vest` on its behalf.
+pub trait UnlockVested<AccountId> {
+	/// Unlock whatever has vested for `who`, if anything.
+	fn unlock_vested(who: &AccountId);
+}
+
+impl<AccountId> UnlockVested<AccountId> for () {
+	fn unlock_vested(_: &AccountId) {}
+}
+
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
+	use codec::Encode;
+	use frame_support::{
+		pallet_prelude::*,
+		sp_runtime::traits::{Hash, Saturating, Zero},
+		traits::{
+			schedule::{DispatchTime, Named as ScheduleNamed, LOWEST_PRIORITY},
+			Currency, ReservableCurrency,
+		},
+	};
 	use frame_system::pallet_prelude::*;
 	use sp_std::vec::Vec; // Step 3.1 will include this in `Cargo.toml`
 
+	type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
 	pub trait Config: frame_system::Config {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 
-		/// The minimum length a name may be.
+		/// The minimum length a claim may be, unless overridden at genesis.
 		#[pallet::constant]
 		type MinLength: Get<u32>;
 
-		/// The maximum length a name may be.
+		/// The maximum length a claim may be, unless overridden at genesis.
 		#[pallet::constant]
 		type MaxLength: Get<u32>;
+
+		/// Origin allowed to revoke and freeze any claim, to change the claim length limits and to
+		/// import claims.
+		type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The overarching call type, for scheduling claim actions.
+		type Call: From<Call<Self>>;
+
+		/// The overarching origin of all pallets, for scheduling claim actions.
+		type PalletsOrigin: From<frame_system::RawOrigin<Self::AccountId>>;
+
+		/// The currency commitment deposits are reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Unlocks vested funds before a deposit is reserved, so that deposits may come from
+		/// funds that have vested but are still locked. Funds still vesting cannot be reserved.
+		type Vesting: super::UnlockVested<Self::AccountId>;
+
+		/// The deposit reserved for a commitment until it is revealed or expires.
+		#[pallet::constant]
+		type CommitmentDeposit: Get<BalanceOf<Self>>;
+
+		/// The number of blocks a commitment may be revealed in.
+		#[pallet::constant]
+		type RevealWindow: Get<Self::BlockNumber>;
+
+		/// The maximum number of commitments made in one block, which bounds the work of
+		/// expiring them.
+		#[pallet::constant]
+		type MaxCommitmentsPerBlock: Get<u32>;
+
+		/// The maximum length of the salt a claim is revealed with.
+		#[pallet::constant]
+		type MaxSaltLength: Get<u32>;
+
+		/// The maximum number of claims imported by one `force_import_claims` call.
+		#[pallet::constant]
+		type MaxImportBatch: Get<u32>;
+
+		/// The scheduler running scheduled transfers and revocations.
+		type Scheduler: ScheduleNamed<
+			Self::BlockNumber,
+			<Self as Config>::Call,
+			Self::PalletsOrigin,
+		>;
 	}
 
 	// Pallets use events to inform users when important changes are made.
@@ -45,6 +112,26 @@ pub mod pallet {
 		ClaimCreated(T::AccountId, Vec<u8>),
 		/// Event emitted when a claim is revoked by the owner. [who, claim]
 		ClaimRevoked(T::AccountId, Vec<u8>),
+		/// Event emitted when the claim length limits are changed. [min, max]
+		LengthLimitsSet(u32, u32),
+		/// Event emitted when a claim is transferred. [from, to, claim]
+		ClaimTransferred(T::AccountId, T::AccountId, Vec<u8>),
+		/// Event emitted when a transfer is scheduled by the owner. [who, claim, dest, at]
+		TransferScheduled(T::AccountId, Vec<u8>, T::AccountId, T::BlockNumber),
+		/// Event emitted when a revocation is scheduled by the owner. [who, claim, at]
+		RevocationScheduled(T::AccountId, Vec<u8>, T::BlockNumber),
+		/// Event emitted when a scheduled action is cancelled by the owner. [who, claim]
+		ScheduleCancelled(T::AccountId, Vec<u8>),
+		/// Event emitted when a claim is committed to without disclosing it. [who, commitment]
+		ClaimCommitted(T::AccountId, T::Hash),
+		/// Event emitted when a commitment was not revealed in time. [who, commitment]
+		CommitmentExpired(T::AccountId, T::Hash),
+		/// Event emitted when a claim is imported from another registry. [who, claim, timestamp]
+		ClaimImported(T::AccountId, Vec<u8>, u64),
+		/// Event emitted when a claim is frozen. [claim]
+		ClaimFrozen(Vec<u8>),
+		/// Event emitted when a claim is thawed. [claim]
+		ClaimThawed(Vec<u8>),
 	}
 
 	// Errors inform users that something went wrong.
@@ -60,6 +147,30 @@ pub mod pallet {
 		TooShort,
 		/// A name is too long.
 		TooLong,
+		/// The minimum length exceeds the maximum length.
+		InvalidLengthLimits,
+		/// The scheduled block is not in the future.
+		ScheduledInThePast,
+		/// An action is already scheduled for the claim.
+		AlreadyScheduled,
+		/// No action is scheduled for the claim.
+		NotScheduled,
+		/// The commitment has already been made by the sender.
+		CommitmentAlreadyExists,
+		/// The sender made no such commitment, or it has expired.
+		NoSuchCommitment,
+		/// The account cannot cover the commitment deposit.
+		InsufficientBalance,
+		/// More claims than `MaxImportBatch` are imported at once.
+		TooManyClaims,
+		/// The block already holds `MaxCommitmentsPerBlock` commitments.
+		TooManyCommitments,
+		/// The salt is longer than `MaxSaltLength`.
+		SaltTooLong,
+		/// The claim is frozen, so it cannot change hands.
+		ClaimFrozen,
+		/// The claim is not frozen.
+		NotFrozen,
 	}
 
 	#[pallet::pallet]
@@ -69,8 +180,97 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type Proofs<T: Config> = StorageMap<_, Blake2_128Concat, Vec<u8>, (T::AccountId, T::BlockNumber), ValueQuery>;
 
+	/// The block each claim takes priority from: when it was created, or committed to if it was
+	/// revealed. Unlike the block in `Proofs`, it is kept when the claim is transferred.
+	///
+	/// Claims without an entry predate commit-reveal, and with it every commitment, or were
+	/// imported from a registry that predates the chain. Either way they take priority from
+	/// block 0, over every commitment.
+	#[pallet::storage]
+	pub(super) type ClaimedAt<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, T::BlockNumber, OptionQuery>;
+
+	/// Unrevealed commitments of each committer: the block committed at and the reserved deposit.
+	///
+	/// Commitments are kept per committer, so that copying a pending commitment from the pool
+	/// does not keep its committer from making it.
+	#[pallet::storage]
+	pub(super) type Commitments<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::Hash,
+		(T::BlockNumber, BalanceOf<T>),
+		OptionQuery,
+	>;
+
+	/// The commitments expiring at a block with their committers, at most
+	/// `MaxCommitmentsPerBlock`.
+	#[pallet::storage]
+	pub(super) type CommitmentExpiries<T: Config> =
+		StorageMap<_, Twox64Concat, T::BlockNumber, Vec<(T::AccountId, T::Hash)>, ValueQuery>;
+
+	/// When imported claims were originally made, in milliseconds since the Unix epoch.
+	#[pallet::storage]
+	pub(super) type ImportedTimestamps<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, u64, OptionQuery>;
+
+	/// Claims that can neither be transferred nor revoked by their owner, e.g. while disputed.
+	#[pallet::storage]
+	pub(super) type FrozenClaims<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, (), OptionQuery>;
+
+	/// Claim length limits `(min, max)` set at genesis, overriding `MinLength` and `MaxLength`.
+	#[pallet::storage]
+	pub(super) type LengthLimits<T: Config> = StorageValue<_, (u32, u32), OptionQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		/// Claim length limits `(min, max)`; `None` keeps the runtime's `MinLength` and `MaxLength`.
+		pub length_limits: Option<(u32, u32)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self { length_limits: None }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			if let Some((min, max)) = self.length_limits {
+				assert!(min <= max, "Claim length minimum must not exceed the maximum");
+				LengthLimits::<T>::put((min, max));
+			}
+		}
+	}
+
 	#[pallet::hooks]
-	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Expire the commitments that were not revealed in time and refund their deposits, at
+		/// most `MaxCommitmentsPerBlock`.
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let expiring = CommitmentExpiries::<T>::take(n);
+			let count = expiring.len() as Weight;
+			for (who, commitment) in expiring {
+				// The commitment may have been revealed and made again since.
+				match Commitments::<T>::get(&who, &commitment) {
+					Some((committed_at, deposit))
+						if committed_at.saturating_add(T::RevealWindow::get()) <= n =>
+					{
+						Commitments::<T>::remove(&who, &commitment);
+						T::Currency::unreserve(&who, deposit);
+						Self::deposit_event(Event::CommitmentExpired(who, commitment));
+					},
+					_ => {},
+				}
+			}
+			T::DbWeight::get().reads_writes(1 + 2 * count, 1 + 2 * count)
+		}
+	}
 
 	// Dispatchable functions allow users to interact with the pallet and invoke state changes.
     // These functions materialize as "extrinsics", which are often compared to transactions.
@@ -88,20 +288,7 @@ pub mod pallet {
 			// https://docs.substrate.io/v3/runtime/origins
 			let sender = ensure_signed(origin)?;
 
-			ensure!(proof.len() >= T::MinLength::get() as usize, Error::<T>::TooShort);
-			ensure!(proof.len() <= T::MaxLength::get() as usize, Error::<T>::TooLong);
-
-			// Verify that the specified proof has not already been claimed.
-			ensure!(!Proofs::<T>::contains_key(&proof), Error::<T>::ProofAlreadyClaimed);
-
-			// Get the block number from the FRAME System pallet.
-			let current_block = <frame_system::Pallet<T>>::block_number();
-
-			// Store the proof with the sender and block number.
-			Proofs::<T>::insert(&proof, (&sender, current_block));
-
-			// Emit an event that the claim was created.
-			Self::deposit_event(Event::ClaimCreated(sender, proof));
+			Self::do_create_claim(sender, proof)?;
 
 			Ok(().into())
 		}
@@ -116,8 +303,8 @@ pub mod pallet {
 			// https://docs.substrate.io/v3/runtime/origins
 			let sender = ensure_signed(origin)?;
 
-			ensure!(proof.len() >= T::MinLength::get() as usize, Error::<T>::TooShort);
-			ensure!(proof.len() <= T::MaxLength::get() as usize, Error::<T>::TooLong);
+			ensure!(proof.len() >= Self::min_length() as usize, Error::<T>::TooShort);
+			ensure!(proof.len() <= Self::max_length() as usize, Error::<T>::TooLong);
 
 			// Verify that the specified proof has been claimed.
 			ensure!(Proofs::<T>::contains_key(&proof), Error::<T>::NoSuchProof);
@@ -127,9 +314,13 @@ pub mod pallet {
 
 			// Verify that sender of the current call is the claim owner.
 			ensure!(sender == owner, Error::<T>::NotProofOwner);
+			ensure!(!FrozenClaims::<T>::contains_key(&proof), Error::<T>::ClaimFrozen);
 
 			// Remove claim from storage.
 			Proofs::<T>::remove(&proof);
+			ClaimedAt::<T>::remove(&proof);
+			ImportedTimestamps::<T>::remove(&proof);
+			Self::cancel_schedule(&proof);
 
 			// Emit an event that the claim was erased.
 			Self::deposit_event(Event::ClaimRevoked(sender, proof));
@@ -145,16 +336,378 @@ pub mod pallet {
 		) -> DispatchResultWithPostInfo {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(claim.len() >= T::MinLength::get() as usize, Error::<T>::TooShort);
-			ensure!(claim.len() <= T::MaxLength::get() as usize, Error::<T>::TooLong);
+			ensure!(claim.len() >= Self::min_length() as usize, Error::<T>::TooShort);
+			ensure!(claim.len() <= Self::max_length() as usize, Error::<T>::TooLong);
+
+			let (owner, _) = Self::proof_of(&claim).ok_or(Error::<T>::NoSuchProof)?;
+			ensure!(owner == sender, Error::<T>::NotProofOwner);
+			ensure!(!FrozenClaims::<T>::contains_key(&claim), Error::<T>::ClaimFrozen);
+
+			Proofs::<T>::insert(&claim, (&dest, frame_system::Pallet::<T>::block_number()));
+			// The new owner starts without the previous owner's scheduled action.
+			Self::cancel_schedule(&claim);
+
+			Self::deposit_event(Event::ClaimTransferred(sender, dest, claim));
+
+			Ok(().into())
+		}
+
+		/// Schedule a transfer of `claim` to `dest` at block `at`, e.g. to release an escrow.
+		///
+		/// The transfer is dispatched as the current owner and is cancelled if the claim changes
+		/// hands in the meantime. It fails if the claim is frozen by then.
+		#[pallet::weight(10_000)]
+		pub fn schedule_transfer(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+			dest: T::AccountId,
+			at: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+
+			let call = Call::<T>::transfer_clain(claim.clone(), dest.clone());
+			Self::schedule(&sender, &claim, at, call)?;
+
+			Self::deposit_event(Event::TransferScheduled(sender, claim, dest, at));
+
+			Ok(().into())
+		}
+
+		/// Schedule a revocation of `claim` at block `at`.
+		///
+		/// The revocation is dispatched as the current owner and is cancelled if the claim changes
+		/// hands in the meantime. It fails if the claim is frozen by then.
+		#[pallet::weight(10_000)]
+		pub fn schedule_revoke(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+			at: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+
+			let call = Call::<T>::revoke_claim(claim.clone());
+			Self::schedule(&sender, &claim, at, call)?;
+
+			Self::deposit_event(Event::RevocationScheduled(sender, claim, at));
+
+			Ok(().into())
+		}
+
+		/// Cancel the action scheduled for `claim`. Only the current owner may cancel it.
+		#[pallet::weight(10_000)]
+		pub fn cancel_scheduled(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+
+			let (owner, _) = Self::proof_of(&claim).ok_or(Error::<T>::NoSuchProof)?;
+			ensure!(sender == owner, Error::<T>::NotProofOwner);
+
+			T::Scheduler::cancel_named(Self::schedule_id(&claim))
+				.map_err(|_| Error::<T>::NotScheduled)?;
+
+			Self::deposit_event(Event::ScheduleCancelled(sender, claim));
+
+			Ok(().into())
+		}
+
+		/// Commit to a claim without disclosing it, where `commitment` is `H(claim ++ salt)`.
+		///
+		/// A deposit is reserved until the claim is revealed with `reveal_claim`, which must
+		/// happen within `RevealWindow` blocks. The revealed claim dates from this block. The
+		/// deposit may come from funds that have vested but are still locked.
+		#[pallet::weight(10_000)]
+		pub fn commit_claim(
+			origin: OriginFor<T>,
+			commitment: T::Hash,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(
+				!Commitments::<T>::contains_key(&sender, &commitment),
+				Error::<T>::CommitmentAlreadyExists
+			);
+			let current_block = <frame_system::Pallet<T>>::block_number();
+			let expiry = current_block.saturating_add(T::RevealWindow::get());
+			let expiring = CommitmentExpiries::<T>::decode_len(expiry).unwrap_or(0);
+			ensure!(
+				expiring < T::MaxCommitmentsPerBlock::get() as usize,
+				Error::<T>::TooManyCommitments
+			);
+
+			let deposit = T::CommitmentDeposit::get();
+			if !T::Currency::can_reserve(&sender, deposit) {
+				T::Vesting::unlock_vested(&sender);
+			}
+			T::Currency::reserve(&sender, deposit).map_err(|_| Error::<T>::InsufficientBalance)?;
+
+			Commitments::<T>::insert(&sender, &commitment, (current_block, deposit));
+			CommitmentExpiries::<T>::append(expiry, (&sender, &commitment));
 
-			let (owner1, _) = Proofs::<T>::get(&claim)/*.ok_or(Error::<T>::ProofAlreadyClaimed)*/;
-			ensure!(owner1 == sender, Error::<T>::NotProofOwner);
+			Self::deposit_event(Event::ClaimCommitted(sender, commitment));
+
+			Ok(().into())
+		}
+
+		/// Reveal a claim committed to with `commit_claim`, and refund the deposit.
+		///
+		/// The claim takes priority over claims of the same hash made after the commitment.
+		#[pallet::weight(10_000)]
+		pub fn reveal_claim(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+			salt: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(claim.len() >= Self::min_length() as usize, Error::<T>::TooShort);
+			ensure!(claim.len() <= Self::max_length() as usize, Error::<T>::TooLong);
+			ensure!(salt.len() <= T::MaxSaltLength::get() as usize, Error::<T>::SaltTooLong);
+
+			let commitment = T::Hashing::hash(&[&claim[..], &salt[..]].concat());
+			let (committed_at, deposit) =
+				Commitments::<T>::get(&sender, &commitment).ok_or(Error::<T>::NoSuchCommitment)?;
+
+			// A claim made after the commitment is superseded by it. Transfers do not make a claim
+			// any younger.
+			if let Some((owner, _)) = Self::proof_of(&claim) {
+				ensure!(Self::claimed_at(&claim) > committed_at, Error::<T>::ProofAlreadyClaimed);
+				ensure!(!FrozenClaims::<T>::contains_key(&claim), Error::<T>::ClaimFrozen);
+				ImportedTimestamps::<T>::remove(&claim);
+				Self::cancel_schedule(&claim);
+				Self::deposit_event(Event::ClaimRevoked(owner, claim.clone()));
+			}
+
+			Commitments::<T>::remove(&sender, &commitment);
+			T::Currency::unreserve(&sender, deposit);
+			Proofs::<T>::insert(&claim, (&sender, committed_at));
+			ClaimedAt::<T>::insert(&claim, committed_at);
+
+			Self::deposit_event(Event::ClaimCreated(sender, claim));
+
+			Ok(().into())
+		}
+
+		/// Revoke `claim` regardless of its owner, e.g. to settle a dispute.
+		#[pallet::weight(10_000)]
+		pub fn force_revoke_claim(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			let (owner, _) = Self::proof_of(&claim).ok_or(Error::<T>::NoSuchProof)?;
+			Proofs::<T>::remove(&claim);
+			ClaimedAt::<T>::remove(&claim);
+			ImportedTimestamps::<T>::remove(&claim);
+			FrozenClaims::<T>::remove(&claim);
+			Self::cancel_schedule(&claim);
+
+			Self::deposit_event(Event::ClaimRevoked(owner, claim));
+
+			Ok(().into())
+		}
+
+		/// Freeze `claim`, so that its owner can neither transfer nor revoke it, directly or by a
+		/// scheduled action, until it is thawed.
+		#[pallet::weight(10_000)]
+		pub fn force_freeze_claim(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			T::ForceOrigin::ensure_origin(origin)?;
 
 			ensure!(Proofs::<T>::contains_key(&claim), Error::<T>::NoSuchProof);
+			FrozenClaims::<T>::insert(&claim, ());
+
+			Self::deposit_event(Event::ClaimFrozen(claim));
 
-			Proofs::<T>::insert(&claim, (dest, frame_system::Pallet::<T>::block_number()));
 			Ok(().into())
 		}
+
+		/// Thaw `claim`, frozen by `force_freeze_claim`.
+		#[pallet::weight(10_000)]
+		pub fn force_thaw_claim(
+			origin: OriginFor<T>,
+			claim: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			ensure!(FrozenClaims::<T>::contains_key(&claim), Error::<T>::NotFrozen);
+			FrozenClaims::<T>::remove(&claim);
+
+			Self::deposit_event(Event::ClaimThawed(claim));
+
+			Ok(().into())
+		}
+
+		/// Import `(claim, owner, timestamp)` claims from another registry, where `timestamp` is
+		/// when the claim was originally made, in milliseconds since the Unix epoch.
+		///
+		/// Claims that already exist are skipped, so an interrupted import may be resumed.
+		/// Imported claims take priority over every commitment, as they predate the chain.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(1, 2).saturating_mul(claims.len() as Weight)
+		)]
+		pub fn force_import_claims(
+			origin: OriginFor<T>,
+			claims: Vec<(Vec<u8>, T::AccountId, u64)>,
+		) -> DispatchResultWithPostInfo {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			ensure!(claims.len() <= T::MaxImportBatch::get() as usize, Error::<T>::TooManyClaims);
+			let (min, max) = (Self::min_length() as usize, Self::max_length() as usize);
+			for (claim, _, _) in &claims {
+				ensure!(claim.len() >= min, Error::<T>::TooShort);
+				ensure!(claim.len() <= max, Error::<T>::TooLong);
+			}
+
+			let current_block = <frame_system::Pallet<T>>::block_number();
+			for (claim, owner, timestamp) in claims {
+				if Proofs::<T>::contains_key(&claim) {
+					continue
+				}
+				// No `ClaimedAt` entry: no commitment can supersede an imported claim.
+				Proofs::<T>::insert(&claim, (&owner, current_block));
+				ImportedTimestamps::<T>::insert(&claim, timestamp);
+				Self::deposit_event(Event::ClaimImported(owner, claim, timestamp));
+			}
+
+			Ok(().into())
+		}
+
+		/// Replace the claim length limits. Existing claims are not affected.
+		#[pallet::weight(10_000)]
+		pub fn force_set_length_limits(
+			origin: OriginFor<T>,
+			min: u32,
+			max: u32,
+		) -> DispatchResultWithPostInfo {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			ensure!(min <= max, Error::<T>::InvalidLengthLimits);
+			LengthLimits::<T>::put((min, max));
+
+			Self::deposit_event(Event::LengthLimitsSet(min, max));
+
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The owner of `claim` and the block it was claimed at, if it has been claimed.
+		pub fn proof_of(claim: &[u8]) -> Option<(T::AccountId, T::BlockNumber)> {
+			if Proofs::<T>::contains_key(claim) {
+				Some(Proofs::<T>::get(claim))
+			} else {
+				None
+			}
+		}
+
+		/// Whether `claim` is frozen by `force_freeze_claim`.
+		pub fn is_frozen(claim: &[u8]) -> bool {
+			FrozenClaims::<T>::contains_key(claim)
+		}
+
+		/// The block `claim` takes priority from, which transfers do not change.
+		pub fn claimed_at(claim: &[u8]) -> T::BlockNumber {
+			ClaimedAt::<T>::get(claim).unwrap_or_else(Zero::zero)
+		}
+
+		/// When `claim` was originally made, if it was imported from another registry.
+		pub fn imported_timestamp(claim: &[u8]) -> Option<u64> {
+			ImportedTimestamps::<T>::get(claim)
+		}
+
+		/// The prefix of the raw storage keys of `Proofs`, for iterating over all claims.
+		///
+		/// Each key is the prefix, the `Blake2_128` hash of the encoded claim and the encoded
+		/// claim; each value is the encoded `(owner, block)`.
+		pub fn proofs_prefix() -> [u8; 32] {
+			<Proofs<T> as frame_support::storage::StoragePrefixedMap<_>>::final_prefix()
+		}
+
+		/// The raw storage key of `claim` in `Proofs`, as used in storage read proofs.
+		pub fn proof_key(claim: &[u8]) -> Vec<u8> {
+			Proofs::<T>::hashed_key_for(claim)
+		}
+
+		/// The raw storage key of `LengthLimits`, whose value is the encoded `(min, max)` limits
+		/// set at genesis or by `force_set_length_limits`.
+		pub fn length_limits_key() -> Vec<u8> {
+			LengthLimits::<T>::hashed_key().to_vec()
+		}
+
+		/// The raw storage key of `claim` in `ImportedTimestamps`, whose value is the encoded
+		/// `u64` timestamp.
+		pub fn imported_timestamp_key(claim: &[u8]) -> Vec<u8> {
+			ImportedTimestamps::<T>::hashed_key_for(claim)
+		}
+
+		/// Claim `proof` for `owner`, as the `create_claim` call does.
+		pub fn do_create_claim(owner: T::AccountId, proof: Vec<u8>) -> Result<(), Error<T>> {
+			ensure!(proof.len() >= Self::min_length() as usize, Error::<T>::TooShort);
+			ensure!(proof.len() <= Self::max_length() as usize, Error::<T>::TooLong);
+
+			// Verify that the specified proof has not already been claimed.
+			ensure!(!Proofs::<T>::contains_key(&proof), Error::<T>::ProofAlreadyClaimed);
+
+			// Get the block number from the FRAME System pallet.
+			let current_block = <frame_system::Pallet<T>>::block_number();
+
+			// Store the proof with the owner and block number.
+			Proofs::<T>::insert(&proof, (&owner, current_block));
+			ClaimedAt::<T>::insert(&proof, current_block);
+
+			// Emit an event that the claim was created.
+			Self::deposit_event(Event::ClaimCreated(owner, proof));
+
+			Ok(())
+		}
+
+		/// The name of the schedule of `claim`; there is at most one scheduled action per claim.
+		fn schedule_id(claim: &[u8]) -> Vec<u8> {
+			(b"poe/schedule", claim).encode()
+		}
+
+		/// Schedule `call` for `claim` at block `at`, dispatched as `owner`.
+		fn schedule(
+			owner: &T::AccountId,
+			claim: &[u8],
+			at: T::BlockNumber,
+			call: Call<T>,
+		) -> DispatchResult {
+			let (current_owner, _) = Self::proof_of(claim).ok_or(Error::<T>::NoSuchProof)?;
+			ensure!(owner == &current_owner, Error::<T>::NotProofOwner);
+			ensure!(at > frame_system::Pallet::<T>::block_number(), Error::<T>::ScheduledInThePast);
+
+			T::Scheduler::schedule_named(
+				Self::schedule_id(claim),
+				DispatchTime::At(at),
+				None,
+				LOWEST_PRIORITY,
+				frame_system::RawOrigin::Signed(owner.clone()).into(),
+				call.into(),
+			)
+			.map_err(|_| Error::<T>::AlreadyScheduled)?;
+
+			Ok(())
+		}
+
+		/// Cancel the action scheduled for `claim`, if any, as it changes hands.
+		fn cancel_schedule(claim: &[u8]) {
+			let _ = T::Scheduler::cancel_named(Self::schedule_id(claim));
+		}
+
+		/// The minimum length a claim may be.
+		pub fn min_length() -> u32 {
+			LengthLimits::<T>::get().map_or_else(T::MinLength::get, |(min, _)| min)
+		}
+
+		/// The maximum length a claim may be.
+		pub fn max_length() -> u32 {
+			LengthLimits::<T>::get().map_or_else(T::MaxLength::get, |(_, max)| max)
+		}
 	}
 }
@@ -1,5 +1,5 @@
 use crate as pallet_template;
-use frame_support::parameter_types;
+use frame_support::{parameter_types, traits::GenesisBuild, weights::Weight};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
@@ -18,7 +18,9 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		TemplateModule: pallet_template::{Pallet, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Scheduler: pallet_scheduler::{Pallet, Call, Storage, Event<T>},
+		TemplateModule: pallet_template::{Pallet, Call, Config, Storage, Event<T>},
 	}
 );
 
@@ -27,6 +29,11 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 	pub const MinPoeLength: u32 = 8;
 	pub const MaxPoeLength: u32 = 32;
+	pub const CommitmentDeposit: u64 = 10;
+	pub const RevealWindow: u64 = 5;
+	pub const MaxCommitmentsPerBlock: u32 = 2;
+	pub const MaxSaltLength: u32 = 8;
+	pub const MaxImportBatch: u32 = 3;
 }
 
 impl system::Config for Test {
@@ -47,7 +54,7 @@ impl system::Config for Test {
 	type BlockHashCount = BlockHashCount;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -55,13 +62,70 @@ impl system::Config for Test {
 	type OnSetCode = ();
 }
 
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MaximumSchedulerWeight: Weight = 1_000_000_000;
+	pub const MaxScheduledPerBlock: u32 = 10;
+}
+
+impl pallet_scheduler::Config for Test {
+	type Event = Event;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = frame_system::EnsureRoot<u64>;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type WeightInfo = ();
+}
+
 impl pallet_template::Config for Test {
 	type Event = Event;
 	type MinLength = MinPoeLength;
 	type MaxLength = MaxPoeLength;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type Call = Call;
+	type PalletsOrigin = OriginCaller;
+	type Currency = Balances;
+	type Vesting = ();
+	type CommitmentDeposit = CommitmentDeposit;
+	type RevealWindow = RevealWindow;
+	type MaxCommitmentsPerBlock = MaxCommitmentsPerBlock;
+	type MaxSaltLength = MaxSaltLength;
+	type MaxImportBatch = MaxImportBatch;
+	type Scheduler = Scheduler;
 }
 
-// Build genesis storage according to the mock runtime.
+// Build genesis storage according to the mock runtime, endowing accounts 1, 2 and 3.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(1, 100), (2, 100), (3, 100)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	storage.into()
+}
+
+// Run the scheduler and the pallet hooks for every block up to and including `n`.
+pub fn run_to_block(n: u64) {
+	use frame_support::traits::OnInitialize;
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Scheduler::on_initialize(System::block_number());
+		TemplateModule::on_initialize(System::block_number());
+	}
 }
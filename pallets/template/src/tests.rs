@@ -1,6 +1,7 @@
 use frame_support::{assert_noop, assert_ok};
+use sp_runtime::traits::{BadOrigin, BlakeTwo256, Hash};
 
-use crate::{Error, mock::*, Proofs};
+use crate::{Error, mock::*, Commitments, Proofs, ImportedTimestamps};
 
 #[test]
 fn create_claim_works() {
@@ -76,11 +77,483 @@ fn transfer_claim_works() {
 fn transfer_claim_failed_when_claim_is_not_owned() {
 	new_test_ext().execute_with(|| {
 		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1, 0, 2];
+		let _ = TemplateModule::create_claim(Origin::signed(2), claim.clone());
 		assert_noop!(TemplateModule::transfer_clain(Origin::signed(1), claim.clone(), 21 as u64),
 			Error::<Test>::NotProofOwner);
 	})
 }
 
+#[test]
+fn transfer_claim_failed_when_claim_is_not_exist() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1, 0, 2];
+		assert_noop!(TemplateModule::transfer_clain(Origin::signed(1), claim.clone(), 21 as u64),
+			Error::<Test>::NoSuchProof);
+		// Not even for the default account, which a missing claim would decode to.
+		assert_noop!(TemplateModule::transfer_clain(Origin::signed(0), claim.clone(), 21 as u64),
+			Error::<Test>::NoSuchProof);
+	})
+}
+
+#[test]
+fn proof_of_returns_owner_only_for_existing_claims() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		assert_eq!(TemplateModule::proof_of(&claim), None);
+		assert_ok!(TemplateModule::create_claim(Origin::signed(1), claim.clone()));
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 0)));
+		assert_eq!(TemplateModule::proof_key(&claim), Proofs::<Test>::hashed_key_for(&claim));
+	})
+}
+
+#[test]
+fn genesis_length_limits_override_config() {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	frame_support::traits::GenesisBuild::<Test>::assimilate_storage(
+		&crate::GenesisConfig { length_limits: Some((2, 4)) },
+		&mut storage,
+	)
+	.unwrap();
+	sp_io::TestExternalities::from(storage).execute_with(|| {
+		assert_ok!(TemplateModule::create_claim(Origin::signed(1), vec![0, 1]));
+		assert_noop!(
+			TemplateModule::create_claim(Origin::signed(1), vec![0, 1, 0, 1, 0]),
+			Error::<Test>::TooLong
+		);
+	})
+}
+
+#[test]
+fn force_revoke_claim_works() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_noop!(
+			TemplateModule::force_revoke_claim(Origin::signed(2), claim.clone()),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::force_revoke_claim(Origin::root(), claim.clone()));
+		assert_eq!(TemplateModule::proof_of(&claim), None);
+		assert_noop!(
+			TemplateModule::force_revoke_claim(Origin::root(), claim.clone()),
+			Error::<Test>::NoSuchProof
+		);
+	})
+}
+
+#[test]
+fn force_set_length_limits_works() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(TemplateModule::force_set_length_limits(Origin::signed(1), 2, 4), BadOrigin);
+		assert_noop!(
+			TemplateModule::force_set_length_limits(Origin::root(), 4, 2),
+			Error::<Test>::InvalidLengthLimits
+		);
+		assert_ok!(TemplateModule::force_set_length_limits(Origin::root(), 2, 4));
+		assert_eq!((TemplateModule::min_length(), TemplateModule::max_length()), (2, 4));
+	})
+}
+
+#[test]
+fn scheduled_transfer_is_dispatched_at_the_given_block() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_ok!(TemplateModule::schedule_transfer(Origin::signed(1), claim.clone(), 2, 5));
+		run_to_block(4);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 0)));
+		run_to_block(5);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((2, 5)));
+	})
+}
+
+#[test]
+fn scheduled_revoke_is_dispatched_at_the_given_block() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_ok!(TemplateModule::schedule_revoke(Origin::signed(1), claim.clone(), 3));
+		run_to_block(3);
+		assert_eq!(TemplateModule::proof_of(&claim), None);
+	})
+}
+
+#[test]
+fn scheduled_transfer_fails_after_ownership_changed() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_ok!(TemplateModule::schedule_transfer(Origin::signed(1), claim.clone(), 2, 5));
+		assert_ok!(TemplateModule::transfer_clain(Origin::signed(1), claim.clone(), 3));
+		run_to_block(5);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((3, 0)));
+	})
+}
+
+#[test]
+fn schedule_transfer_failed_when_not_owner_or_in_the_past() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_noop!(
+			TemplateModule::schedule_transfer(Origin::signed(2), claim.clone(), 2, 5),
+			Error::<Test>::NotProofOwner
+		);
+		run_to_block(5);
+		assert_noop!(
+			TemplateModule::schedule_transfer(Origin::signed(1), claim.clone(), 2, 5),
+			Error::<Test>::ScheduledInThePast
+		);
+	})
+}
+
+#[test]
+fn only_one_action_can_be_scheduled_per_claim() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_ok!(TemplateModule::schedule_transfer(Origin::signed(1), claim.clone(), 2, 5));
+		assert_noop!(
+			TemplateModule::schedule_revoke(Origin::signed(1), claim.clone(), 6),
+			Error::<Test>::AlreadyScheduled
+		);
+	})
+}
+
+#[test]
+fn cancel_scheduled_works() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_noop!(
+			TemplateModule::cancel_scheduled(Origin::signed(1), claim.clone()),
+			Error::<Test>::NotScheduled
+		);
+		assert_ok!(TemplateModule::schedule_transfer(Origin::signed(1), claim.clone(), 2, 5));
+		assert_noop!(
+			TemplateModule::cancel_scheduled(Origin::signed(2), claim.clone()),
+			Error::<Test>::NotProofOwner
+		);
+		assert_ok!(TemplateModule::cancel_scheduled(Origin::signed(1), claim.clone()));
+		run_to_block(5);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 0)));
+	})
+}
+
+#[test]
+fn frozen_claim_cannot_change_hands() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_noop!(
+			TemplateModule::force_freeze_claim(Origin::signed(1), claim.clone()),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::force_freeze_claim(Origin::root(), claim.clone()));
+		assert!(TemplateModule::is_frozen(&claim));
+
+		assert_noop!(
+			TemplateModule::transfer_clain(Origin::signed(1), claim.clone(), 2),
+			Error::<Test>::ClaimFrozen
+		);
+		assert_noop!(
+			TemplateModule::revoke_claim(Origin::signed(1), claim.clone()),
+			Error::<Test>::ClaimFrozen
+		);
+
+		assert_ok!(TemplateModule::force_thaw_claim(Origin::root(), claim.clone()));
+		assert_noop!(
+			TemplateModule::force_thaw_claim(Origin::root(), claim.clone()),
+			Error::<Test>::NotFrozen
+		);
+		assert_ok!(TemplateModule::transfer_clain(Origin::signed(1), claim.clone(), 2));
+	})
+}
+
+#[test]
+fn scheduled_transfer_fails_while_frozen() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_ok!(TemplateModule::schedule_transfer(Origin::signed(1), claim.clone(), 2, 5));
+		assert_ok!(TemplateModule::force_freeze_claim(Origin::root(), claim.clone()));
+		run_to_block(5);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 0)));
+	})
+}
+
+#[test]
+fn transfer_cancels_the_scheduled_action() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(1), claim.clone());
+		assert_ok!(TemplateModule::schedule_revoke(Origin::signed(1), claim.clone(), 5));
+		assert_ok!(TemplateModule::transfer_clain(Origin::signed(1), claim.clone(), 2));
+
+		// The new owner may schedule an action of their own.
+		assert_ok!(TemplateModule::schedule_transfer(Origin::signed(2), claim.clone(), 3, 6));
+		run_to_block(5);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((2, 0)));
+		run_to_block(6);
+		assert_eq!(TemplateModule::proof_of(&claim), Some((3, 6)));
+	})
+}
+
+fn commitment_of(claim: &[u8], salt: &[u8]) -> sp_core::H256 {
+	BlakeTwo256::hash(&[claim, salt].concat())
+}
+
+#[test]
+fn commit_and_reveal_claim_works() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let commitment = commitment_of(&claim, b"salt");
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment));
+		assert_eq!(Balances::usable_balance(1), 90);
+
+		run_to_block(3);
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(1), claim.clone(), b"pepper".to_vec()),
+			Error::<Test>::NoSuchCommitment
+		);
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(2), claim.clone(), b"salt".to_vec()),
+			Error::<Test>::NoSuchCommitment
+		);
+		assert_ok!(TemplateModule::reveal_claim(
+			Origin::signed(1),
+			claim.clone(),
+			b"salt".to_vec()
+		));
+		// The claim dates from the commitment.
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 0)));
+		assert_eq!(Balances::usable_balance(1), 100);
+	})
+}
+
+#[test]
+fn commit_claim_failed_when_commitment_already_exists() {
+	new_test_ext().execute_with(|| {
+		let commitment = commitment_of(&[0, 1, 0, 1, 0, 1, 0, 1], b"salt");
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment));
+		assert_noop!(
+			TemplateModule::commit_claim(Origin::signed(1), commitment),
+			Error::<Test>::CommitmentAlreadyExists
+		);
+	})
+}
+
+#[test]
+fn copied_commitment_does_not_lock_out_committer() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let commitment = commitment_of(&claim, b"salt");
+		// Account 2 copies the commitment from the pool and gets it in first.
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(2), commitment));
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment));
+
+		run_to_block(3);
+		assert_ok!(TemplateModule::reveal_claim(
+			Origin::signed(1),
+			claim.clone(),
+			b"salt".to_vec()
+		));
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 0)));
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(2), claim.clone(), b"salt".to_vec()),
+			Error::<Test>::ProofAlreadyClaimed
+		);
+
+		// The copy expires like any other commitment.
+		run_to_block(5);
+		assert_eq!(Balances::usable_balance(2), 100);
+		assert!(!Commitments::<Test>::contains_key(2, commitment));
+	})
+}
+
+#[test]
+fn revealed_claim_supersedes_later_claims_only() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(2), claim.clone());
+		run_to_block(1);
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(&claim, b"salt")));
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(1), claim.clone(), b"salt".to_vec()),
+			Error::<Test>::ProofAlreadyClaimed
+		);
+
+		let claim = vec![0, 2, 0, 2, 0, 2, 0, 2];
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(&claim, b"salt")));
+		run_to_block(2);
+		let _ = TemplateModule::create_claim(Origin::signed(2), claim.clone());
+		assert_ok!(TemplateModule::reveal_claim(
+			Origin::signed(1),
+			claim.clone(),
+			b"salt".to_vec()
+		));
+		assert_eq!(TemplateModule::proof_of(&claim), Some((1, 1)));
+	})
+}
+
+#[test]
+fn transferred_claim_keeps_its_priority() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let _ = TemplateModule::create_claim(Origin::signed(2), claim.clone());
+		run_to_block(1);
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(&claim, b"salt")));
+		run_to_block(2);
+		assert_ok!(TemplateModule::transfer_clain(Origin::signed(2), claim.clone(), 3));
+
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(1), claim.clone(), b"salt".to_vec()),
+			Error::<Test>::ProofAlreadyClaimed
+		);
+		assert_eq!(TemplateModule::claimed_at(&claim), 0);
+	})
+}
+
+#[test]
+fn reveal_claim_failed_when_salt_is_too_long() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		let salt = b"much too salty".to_vec();
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(&claim, &salt)));
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(1), claim.clone(), salt),
+			Error::<Test>::SaltTooLong
+		);
+	})
+}
+
+#[test]
+fn commit_claim_failed_when_block_is_full() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(b"claim 1", b"")));
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(2), commitment_of(b"claim 2", b"")));
+		assert_noop!(
+			TemplateModule::commit_claim(Origin::signed(1), commitment_of(b"claim 3", b"")),
+			Error::<Test>::TooManyCommitments
+		);
+		run_to_block(1);
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(b"claim 3", b"")));
+	})
+}
+
+#[test]
+fn unrevealed_commitment_expires_and_refunds_deposit() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(&claim, b"salt")));
+		run_to_block(4);
+		assert_eq!(Balances::usable_balance(1), 90);
+		run_to_block(5);
+		assert_eq!(Balances::usable_balance(1), 100);
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(1), claim.clone(), b"salt".to_vec()),
+			Error::<Test>::NoSuchCommitment
+		);
+	})
+}
+
+#[test]
+fn commit_claim_failed_when_deposit_not_covered() {
+	new_test_ext().execute_with(|| {
+		for salt in 0..10u8 {
+			let commitment = commitment_of(&[0, 1, 0, 1, 0, 1, 0, 1], &[salt]);
+			assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment));
+		}
+		assert_eq!(Balances::usable_balance(1), 0);
+		assert_noop!(
+			TemplateModule::commit_claim(Origin::signed(1), commitment_of(&[0, 1], b"salt")),
+			Error::<Test>::InsufficientBalance
+		);
+		assert_noop!(
+			TemplateModule::commit_claim(Origin::signed(4), commitment_of(&[0, 1], b"salt")),
+			Error::<Test>::InsufficientBalance
+		);
+	})
+}
+
+#[test]
+fn force_import_claims_works() {
+	new_test_ext().execute_with(|| {
+		let claims = vec![(vec![1; 8], 2, 1_000), (vec![2; 8], 3, 2_000)];
+		assert_noop!(
+			TemplateModule::force_import_claims(Origin::signed(1), claims.clone()),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::force_import_claims(Origin::root(), claims));
+
+		assert_eq!(TemplateModule::proof_of(&[1; 8]), Some((2, 0)));
+		assert_eq!(TemplateModule::proof_of(&[2; 8]), Some((3, 0)));
+		assert_eq!(TemplateModule::imported_timestamp(&[1; 8]), Some(1_000));
+		assert_eq!(TemplateModule::imported_timestamp(&[2; 8]), Some(2_000));
+
+		// Revoking a claim forgets its original timestamp.
+		assert_ok!(TemplateModule::revoke_claim(Origin::signed(2), vec![1; 8]));
+		assert!(!ImportedTimestamps::<Test>::contains_key(vec![1; 8]));
+	})
+}
+
+#[test]
+fn imported_claims_cannot_be_superseded_by_commitments() {
+	new_test_ext().execute_with(|| {
+		let claim = vec![0, 1, 0, 1, 0, 1, 0, 1];
+		run_to_block(1);
+		assert_ok!(TemplateModule::commit_claim(Origin::signed(1), commitment_of(&claim, b"salt")));
+		run_to_block(2);
+		assert_ok!(TemplateModule::force_import_claims(
+			Origin::root(),
+			vec![(claim.clone(), 2, 1_600_000_000_000)]
+		));
+
+		assert_noop!(
+			TemplateModule::reveal_claim(Origin::signed(1), claim.clone(), b"salt".to_vec()),
+			Error::<Test>::ProofAlreadyClaimed
+		);
+		assert_eq!(TemplateModule::claimed_at(&claim), 0);
+	})
+}
+
+#[test]
+fn force_import_claims_skips_existing_claims() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::create_claim(Origin::signed(1), vec![1; 8]));
+		assert_ok!(TemplateModule::force_import_claims(Origin::root(), vec![(vec![2; 8], 3, 5)]));
+
+		let claims = vec![(vec![1; 8], 2, 1_000), (vec![2; 8], 2, 1_000), (vec![3; 8], 2, 1_000)];
+		assert_ok!(TemplateModule::force_import_claims(Origin::root(), claims));
+
+		assert_eq!(TemplateModule::proof_of(&[1; 8]), Some((1, 0)));
+		assert_eq!(TemplateModule::imported_timestamp(&[1; 8]), None);
+		assert_eq!(TemplateModule::proof_of(&[2; 8]), Some((3, 0)));
+		assert_eq!(TemplateModule::imported_timestamp(&[2; 8]), Some(5));
+		assert_eq!(TemplateModule::proof_of(&[3; 8]), Some((2, 0)));
+	})
+}
+
+#[test]
+fn force_import_claims_failed_when_batch_is_invalid() {
+	new_test_ext().execute_with(|| {
+		let too_many = (1..=4u8).map(|i| (vec![i; 8], 1, 0)).collect::<Vec<_>>();
+		assert_noop!(
+			TemplateModule::force_import_claims(Origin::root(), too_many),
+			Error::<Test>::TooManyClaims
+		);
+		let too_short = vec![(vec![1; 8], 1, 0), (vec![2; 2], 1, 0)];
+		assert_noop!(
+			TemplateModule::force_import_claims(Origin::root(), too_short),
+			Error::<Test>::TooShort
+		);
+		assert_noop!(
+			TemplateModule::force_import_claims(Origin::root(), vec![(vec![1; 33], 1, 0)]),
+			Error::<Test>::TooLong
+		);
+	})
+}
+
 // #[test]
 // fn it_works_for_default_value() {
 // 	new_test_ext().execute_with(|| {
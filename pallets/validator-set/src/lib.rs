@@ -0,0 +1,195 @@
+//! Validator set management for a proof-of-authority chain.
+//!
+//! A privileged origin adds and removes validators. Changes are handed to `pallet_session` as
+//! its `SessionManager`, so they are queued at the next session boundary and take effect the
+//! session after that. Validators need to have registered session keys with
+//! `Session::set_keys` before they can be added, and only validators with keys count towards
+//! `MinValidators`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{pallet_prelude::*, traits::ValidatorRegistration};
+	use frame_system::pallet_prelude::*;
+	use sp_staking::SessionIndex;
+	use sp_std::vec::Vec;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to add and remove validators.
+		type AddRemoveOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The minimum number of validators with session keys that must remain in the set.
+		#[pallet::constant]
+		type MinValidators: Get<u32>;
+
+		/// Tells which accounts have registered session keys, usually `pallet_session`.
+		type ValidatorRegistration: ValidatorRegistration<Self::AccountId>;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A validator was added, effective from the session after next. [who]
+		ValidatorAdded(T::AccountId),
+		/// A validator was removed, effective from the session after next. [who]
+		ValidatorRemoved(T::AccountId),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account is already a validator.
+		AlreadyValidator,
+		/// The account is not a validator.
+		NotValidator,
+		/// Removing the validator would leave fewer than `MinValidators` with session keys.
+		TooFewValidators,
+		/// The account has not registered session keys.
+		NoSessionKeys,
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The validator set to hand to the session pallet.
+	#[pallet::storage]
+	#[pallet::getter(fn validators)]
+	pub type Validators<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+
+	/// Whether `Validators` changed since it was last handed to the session pallet.
+	#[pallet::storage]
+	pub(super) type Changed<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The validators of the first sessions.
+		pub initial_validators: Vec<T::AccountId>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { initial_validators: Vec::new() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			assert!(
+				self.initial_validators.len() as u32 >= T::MinValidators::get(),
+				"Fewer initial validators than MinValidators"
+			);
+			Validators::<T>::put(&self.initial_validators);
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Add `validator` to the set from the session after next.
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 2).saturating_add(10_000))]
+		pub fn add_validator(
+			origin: OriginFor<T>,
+			validator: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			T::AddRemoveOrigin::ensure_origin(origin)?;
+			ensure!(T::ValidatorRegistration::is_registered(&validator), Error::<T>::NoSessionKeys);
+
+			Validators::<T>::try_mutate(|validators| -> DispatchResult {
+				ensure!(!validators.contains(&validator), Error::<T>::AlreadyValidator);
+				validators.push(validator.clone());
+				Ok(())
+			})?;
+			Changed::<T>::put(true);
+
+			Self::deposit_event(Event::ValidatorAdded(validator));
+
+			Ok(().into())
+		}
+
+		/// Remove `validator` from the set from the session after next.
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 2).saturating_add(10_000))]
+		pub fn remove_validator(
+			origin: OriginFor<T>,
+			validator: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			T::AddRemoveOrigin::ensure_origin(origin)?;
+
+			Validators::<T>::try_mutate(|validators| -> DispatchResult {
+				let index = validators
+					.iter()
+					.position(|v| v == &validator)
+					.ok_or(Error::<T>::NotValidator)?;
+				// Validators without keys are skipped by the session pallet, so they do not count.
+				let remaining = validators
+					.iter()
+					.filter(|v| *v != &validator && T::ValidatorRegistration::is_registered(v))
+					.count();
+				ensure!(remaining as u32 >= T::MinValidators::get(), Error::<T>::TooFewValidators);
+				validators.remove(index);
+				Ok(())
+			})?;
+			Changed::<T>::put(true);
+
+			Self::deposit_event(Event::ValidatorRemoved(validator));
+
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> pallet_session::SessionManager<T::AccountId> for Pallet<T> {
+		fn new_session(_new_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+			if Changed::<T>::take() {
+				Some(Validators::<T>::get())
+			} else {
+				None
+			}
+		}
+
+		fn new_session_genesis(_new_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+			Some(Validators::<T>::get())
+		}
+
+		fn end_session(_end_index: SessionIndex) {}
+
+		fn start_session(_start_index: SessionIndex) {}
+	}
+
+	/// Validators are their own full identification, for use with `pallet_session::historical`.
+	impl<T: Config> pallet_session::historical::SessionManager<T::AccountId, T::AccountId>
+		for Pallet<T>
+	{
+		fn new_session(new_index: SessionIndex) -> Option<Vec<(T::AccountId, T::AccountId)>> {
+			<Self as pallet_session::SessionManager<_>>::new_session(new_index)
+				.map(|validators| validators.into_iter().map(|v| (v.clone(), v)).collect())
+		}
+
+		fn new_session_genesis(
+			new_index: SessionIndex,
+		) -> Option<Vec<(T::AccountId, T::AccountId)>> {
+			<Self as pallet_session::SessionManager<_>>::new_session_genesis(new_index)
+				.map(|validators| validators.into_iter().map(|v| (v.clone(), v)).collect())
+		}
+
+		fn end_session(_end_index: SessionIndex) {}
+
+		fn start_session(_start_index: SessionIndex) {}
+	}
+}
@@ -0,0 +1,94 @@
+use crate as pallet_validator_set;
+use frame_support::{
+	parameter_types,
+	traits::{GenesisBuild, ValidatorRegistration},
+};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::{cell::RefCell, collections::BTreeSet};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		ValidatorSet: pallet_validator_set::{Pallet, Call, Config<T>, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+	pub const MinValidators: u32 = 1;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::AllowAll;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+thread_local! {
+	static REGISTERED: RefCell<BTreeSet<u64>> = RefCell::new((1..=3).collect());
+}
+
+/// Accounts 1, 2 and 3 start out with session keys.
+pub struct SessionKeys;
+
+impl SessionKeys {
+	pub fn purge(who: u64) {
+		REGISTERED.with(|registered| registered.borrow_mut().remove(&who));
+	}
+}
+
+impl ValidatorRegistration<u64> for SessionKeys {
+	fn is_registered(who: &u64) -> bool {
+		REGISTERED.with(|registered| registered.borrow().contains(who))
+	}
+}
+
+impl pallet_validator_set::Config for Test {
+	type Event = Event;
+	type AddRemoveOrigin = frame_system::EnsureRoot<u64>;
+	type MinValidators = MinValidators;
+	type ValidatorRegistration = SessionKeys;
+}
+
+// Build genesis storage according to the mock runtime, with validators 1 and 2.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_validator_set::GenesisConfig::<Test> { initial_validators: vec![1, 2] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	storage.into()
+}
@@ -0,0 +1,97 @@
+use frame_support::{assert_noop, assert_ok};
+use pallet_session::SessionManager;
+use sp_runtime::traits::BadOrigin;
+
+use crate::{mock::*, Error};
+
+#[test]
+fn genesis_validators_are_used_for_genesis_sessions() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(ValidatorSet::validators(), vec![1, 2]);
+		assert_eq!(ValidatorSet::new_session_genesis(0), Some(vec![1, 2]));
+		assert_eq!(ValidatorSet::new_session(1), None);
+	})
+}
+
+#[test]
+fn add_validator_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(ValidatorSet::add_validator(Origin::root(), 3));
+		assert_eq!(ValidatorSet::validators(), vec![1, 2, 3]);
+	})
+}
+
+#[test]
+fn add_validator_failed_when_already_validator() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			ValidatorSet::add_validator(Origin::root(), 1),
+			Error::<Test>::AlreadyValidator
+		);
+	})
+}
+
+#[test]
+fn add_validator_failed_without_session_keys() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(ValidatorSet::add_validator(Origin::root(), 4), Error::<Test>::NoSessionKeys);
+	})
+}
+
+#[test]
+fn add_validator_failed_when_not_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(ValidatorSet::add_validator(Origin::signed(1), 3), BadOrigin);
+	})
+}
+
+#[test]
+fn remove_validator_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(ValidatorSet::remove_validator(Origin::root(), 1));
+		assert_eq!(ValidatorSet::validators(), vec![2]);
+	})
+}
+
+#[test]
+fn remove_validator_failed_when_not_validator() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			ValidatorSet::remove_validator(Origin::root(), 3),
+			Error::<Test>::NotValidator
+		);
+	})
+}
+
+#[test]
+fn remove_validator_failed_when_too_few_validators() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(ValidatorSet::remove_validator(Origin::root(), 1));
+		assert_noop!(
+			ValidatorSet::remove_validator(Origin::root(), 2),
+			Error::<Test>::TooFewValidators
+		);
+	})
+}
+
+#[test]
+fn remove_validator_failed_when_too_few_validators_have_keys() {
+	new_test_ext().execute_with(|| {
+		SessionKeys::purge(2);
+		assert_noop!(
+			ValidatorSet::remove_validator(Origin::root(), 1),
+			Error::<Test>::TooFewValidators
+		);
+		// Validators without keys can still be removed.
+		assert_ok!(ValidatorSet::remove_validator(Origin::root(), 2));
+	})
+}
+
+#[test]
+fn changes_are_handed_to_the_next_session_once() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(ValidatorSet::add_validator(Origin::root(), 3));
+		assert_eq!(ValidatorSet::new_session(1), Some(vec![1, 2, 3]));
+		assert_eq!(ValidatorSet::new_session(2), None);
+	})
+}
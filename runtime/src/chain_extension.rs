@@ -0,0 +1,105 @@
+//! Chain extension giving ink! contracts access to proof-of-existence claims.
+//!
+//! | id | function | input | output |
+//! |----|----------|-------|--------|
+//! | 1 | `proof_of` | `Vec<u8>` claim | `Option<(AccountId, BlockNumber)>` |
+//! | 2 | `create_claim` | `Vec<u8>` claim, owned by the calling contract | status code |
+//! | 3 | `is_owner` | `(Vec<u8>, AccountId)` | `bool` |
+//!
+//! `create_claim` returns status code `0` on success, and otherwise one of [`PoeStatus`].
+
+use crate::{AccountId, BlockNumber, Runtime, TemplateModule};
+use codec::{Decode, Encode};
+use frame_support::{traits::Get, weights::Weight};
+use pallet_contracts::chain_extension::{
+	BufInBufOutState, ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+};
+use pallet_template::Error as PoeError;
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+/// Status codes of `create_claim`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum PoeStatus {
+	/// The claim is already taken.
+	AlreadyClaimed = 1,
+	/// The claim is shorter than allowed.
+	TooShort = 2,
+	/// The claim is longer than allowed.
+	TooLong = 3,
+	/// Any other failure.
+	Other = 255,
+}
+
+impl From<PoeError<Runtime>> for PoeStatus {
+	fn from(e: PoeError<Runtime>) -> Self {
+		match e {
+			PoeError::ProofAlreadyClaimed => PoeStatus::AlreadyClaimed,
+			PoeError::TooShort => PoeStatus::TooShort,
+			PoeError::TooLong => PoeStatus::TooLong,
+			_ => PoeStatus::Other,
+		}
+	}
+}
+
+/// The longest input accepted: a maximal claim and an account, with some room for encoding.
+const MAX_INPUT_LEN: u32 = 1024;
+
+/// Weight of `create_claim` besides its storage accesses, as for the dispatchable.
+const CREATE_CLAIM_WEIGHT: Weight = 1_000;
+
+/// The proof-of-existence chain extension.
+pub struct PoeExtension;
+
+impl ChainExtension<Runtime> for PoeExtension {
+	fn call<E>(func_id: u32, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+	where
+		E: Ext<T = Runtime>,
+		<E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+	{
+		let db = <Runtime as frame_system::Config>::DbWeight::get();
+		let mut env = env.buf_in_buf_out();
+		match func_id {
+			1 => {
+				env.charge_weight(db.reads(2))?;
+				let claim: Vec<u8> = decode_input(&mut env)?;
+				let proof: Option<(AccountId, BlockNumber)> = TemplateModule::proof_of(&claim);
+				env.write(&proof.encode(), false, None)?;
+			},
+			2 => {
+				env.charge_weight(db.reads_writes(2, 1).saturating_add(CREATE_CLAIM_WEIGHT))?;
+				let claim: Vec<u8> = decode_input(&mut env)?;
+				let owner = env.ext().address().clone();
+				if let Err(e) = TemplateModule::do_create_claim(owner, claim) {
+					return Ok(RetVal::Converging(PoeStatus::from(e) as u32))
+				}
+			},
+			3 => {
+				env.charge_weight(db.reads(2))?;
+				let (claim, account): (Vec<u8>, AccountId) = decode_input(&mut env)?;
+				let is_owner =
+					TemplateModule::proof_of(&claim).map_or(false, |(owner, _)| owner == account);
+				env.write(&is_owner.encode(), false, None)?;
+			},
+			_ => return Err(DispatchError::Other("Unknown PoE chain extension function")),
+		}
+
+		Ok(RetVal::Converging(0))
+	}
+
+	fn enabled() -> bool {
+		true
+	}
+}
+
+/// Read and decode the input of the called function.
+fn decode_input<E, T>(env: &mut Environment<E, BufInBufOutState>) -> Result<T, DispatchError>
+where
+	E: Ext<T = Runtime>,
+	T: Decode,
+{
+	let input = env.read(MAX_INPUT_LEN)?;
+	T::decode(&mut &input[..])
+		.map_err(|_| DispatchError::Other("Invalid PoE chain extension input"))
+}
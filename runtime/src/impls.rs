@@ -0,0 +1,135 @@
+//! Some configurable implementations as associated type for the runtime.
+
+use crate::{AccountId, Authorship, Balances, Treasury};
+use frame_support::traits::{Currency, Imbalance, OnUnbalanced};
+
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Credits imbalances to the author of the current block.
+pub struct Author;
+
+impl OnUnbalanced<NegativeImbalance> for Author {
+	fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+		Balances::resolve_creating(&Authorship::author(), amount);
+	}
+}
+
+/// Splits transaction fees between the treasury and the block author.
+///
+/// Fees go 80% to the treasury and 20% to the author; tips go to the author entirely.
+pub struct DealWithFees;
+
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_unbalanceds<B>(mut fees_then_tips: impl Iterator<Item = NegativeImbalance>) {
+		if let Some(fees) = fees_then_tips.next() {
+			let mut split = fees.ration(80, 20);
+			if let Some(tips) = fees_then_tips.next() {
+				tips.merge_into(&mut split.1);
+			}
+			Treasury::on_unbalanced(split.0);
+			Author::on_unbalanced(split.1);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		BlockWeights, Runtime, System, TargetBlockFullness, TransactionPayment, WeightToFee,
+	};
+	use frame_support::{
+		traits::OnFinalize,
+		weights::{DispatchClass, DispatchInfo, Pays, Weight, WeightToFeePolynomial},
+	};
+	use pallet_transaction_payment::Multiplier;
+	use sp_runtime::traits::{Convert, One};
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into()
+	}
+
+	fn max_normal() -> Weight {
+		BlockWeights::get()
+			.get(DispatchClass::Normal)
+			.max_total
+			.unwrap_or_else(|| BlockWeights::get().max_block)
+	}
+
+	fn update(multiplier: Multiplier) -> Multiplier {
+		<Runtime as pallet_transaction_payment::Config>::FeeMultiplierUpdate::convert(multiplier)
+	}
+
+	/// Fill `blocks` consecutive blocks with `weight` of normal extrinsics.
+	fn run_blocks(blocks: u32, weight: Weight) {
+		for n in 1..=blocks {
+			System::set_block_number(n);
+			System::set_block_consumed_resources(weight, 0);
+			TransactionPayment::on_finalize(n);
+		}
+	}
+
+	#[test]
+	fn multiplier_goes_up_under_sustained_full_blocks() {
+		new_test_ext().execute_with(|| {
+			System::set_block_consumed_resources(max_normal(), 0);
+			let mut multiplier = Multiplier::one();
+			for _ in 0..100 {
+				let next = update(multiplier);
+				assert!(next > multiplier);
+				multiplier = next;
+			}
+		})
+	}
+
+	#[test]
+	fn multiplier_stays_put_at_target_fullness() {
+		new_test_ext().execute_with(|| {
+			System::set_block_consumed_resources(TargetBlockFullness::get() * max_normal(), 0);
+			assert_eq!(update(Multiplier::one()), Multiplier::one());
+		})
+	}
+
+	#[test]
+	fn fees_go_up_under_sustained_full_blocks() {
+		new_test_ext().execute_with(|| {
+			let info = DispatchInfo {
+				weight: 100_000_000,
+				class: DispatchClass::Normal,
+				pays_fee: Pays::Yes,
+			};
+			let initial_fee = TransactionPayment::compute_fee(100, &info, 0);
+			assert_eq!(TransactionPayment::next_fee_multiplier(), Multiplier::one());
+
+			let mut previous_fee = initial_fee;
+			for _ in 0..10 {
+				run_blocks(10, max_normal());
+				let fee = TransactionPayment::compute_fee(100, &info, 0);
+				assert!(fee > previous_fee);
+				previous_fee = fee;
+			}
+		})
+	}
+
+	#[test]
+	fn weight_to_fee_is_increasing() {
+		let base = WeightToFee::calc(&1_000_000);
+		assert!(base > 0);
+		assert!(WeightToFee::calc(&max_normal()) > base);
+	}
+
+	#[test]
+	fn fees_are_split_between_treasury_and_author() {
+		new_test_ext().execute_with(|| {
+			let fees = Balances::issue(10_000);
+			let tips = Balances::issue(1_000);
+			DealWithFees::on_unbalanceds(vec![fees, tips].into_iter());
+
+			assert_eq!(Balances::free_balance(Treasury::account_id()), 8_000);
+			assert_eq!(Balances::free_balance(Authorship::author()), 3_000);
+		})
+	}
+}
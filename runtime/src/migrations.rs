@@ -0,0 +1,57 @@
+//! Storage migrations run by `Executive` on runtime upgrades.
+
+use crate::{AccountId, Balance, Balances, MaxAdditionalFields, MaxRegistrars, Runtime};
+use codec::Encode;
+use frame_support::{
+	storage::migration,
+	traits::{Get, OnRuntimeUpgrade, ReservableCurrency},
+	weights::Weight,
+	StorageHasher, Twox64Concat,
+};
+use pallet_identity::{Data, IdentityInfo, Registration};
+use sp_std::vec::Vec;
+
+/// Carries the nicknames of the removed `Nicks` pallet over into `Identity` display names.
+///
+/// The nick's reserved deposit becomes the identity's deposit, so clearing the identity later
+/// unreserves it as usual. Accounts that already set an identity keep it and get the nick's
+/// deposit back.
+pub struct NicksToIdentity;
+
+impl OnRuntimeUpgrade for NicksToIdentity {
+	fn on_runtime_upgrade() -> Weight {
+		let nicks = migration::storage_key_iter::<AccountId, (Vec<u8>, Balance), Twox64Concat>(
+			b"Nicks", b"NameOf",
+		)
+		.drain();
+
+		let mut reads_writes = 0;
+		for (who, (name, deposit)) in nicks {
+			reads_writes += 1;
+			let key = Twox64Concat::hash(&who.encode());
+			if migration::have_storage_value(b"Identity", b"IdentityOf", &key) {
+				Balances::unreserve(&who, deposit);
+				continue
+			}
+
+			let registration = Registration::<Balance, MaxRegistrars, MaxAdditionalFields> {
+				judgements: Default::default(),
+				deposit,
+				info: IdentityInfo {
+					additional: Default::default(),
+					display: Data::Raw(name),
+					legal: Data::None,
+					web: Data::None,
+					riot: Data::None,
+					email: Data::None,
+					pgp_fingerprint: None,
+					image: Data::None,
+					twitter: Data::None,
+				},
+			};
+			migration::put_storage_value(b"Identity", b"IdentityOf", &key, registration);
+		}
+
+		<Runtime as frame_system::Config>::DbWeight::get().reads_writes(reads_writes, reads_writes)
+	}
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use codec::{Decode, Encode};
+use common::*;
+use node_template_runtime::{AccountId, BlockNumber, Contracts, TemplateModule, UNITS};
+use pallet_contracts_primitives::Code;
+use sp_runtime::DispatchError;
+
+/// A contract calling the chain extension function given by the first four bytes of its input
+/// with the rest of it, and returning the status code followed by the function's output.
+const CALLER: &str = r#"
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_call_chain_extension"
+		(func $seal_call_chain_extension (param i32 i32 i32 i32 i32) (result i32)))
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the input, [4, 4100) the input.
+	(data (i32.const 0) "\00\10")
+	;; [8192, 8196) the length of the output, [8196, 8200) the status, [8200, 12296) the output.
+	(data (i32.const 8192) "\00\10")
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(i32.store (i32.const 8196)
+			(call $seal_call_chain_extension
+				(i32.load (i32.const 4))
+				(i32.const 8)
+				(i32.sub (i32.load (i32.const 0)) (i32.const 4))
+				(i32.const 8200)
+				(i32.const 8192)))
+		(call $seal_return
+			(i32.const 0)
+			(i32.const 8196)
+			(i32.add (i32.const 4) (i32.load (i32.const 8192))))
+	)
+)
+"#;
+
+const GAS_LIMIT: u64 = 100_000_000_000;
+
+const CLAIM: &[u8] = b"a claimed document";
+
+/// Deploy the calling contract as Bob.
+fn deploy() -> AccountId {
+	let wasm = wat::parse_str(CALLER).unwrap();
+	let code = Code::Upload(wasm.into());
+	Contracts::bare_instantiate(
+		account(Bob),
+		100 * UNITS,
+		GAS_LIMIT,
+		code,
+		vec![],
+		vec![],
+		false,
+		false,
+	)
+	.result
+	.expect("the contract is deployed")
+	.account_id
+}
+
+/// Call chain extension function `func_id` with `input` through `contract`, returning the
+/// status code and the output.
+fn call(
+	contract: &AccountId,
+	func_id: u32,
+	input: impl Encode,
+) -> Result<(u32, Vec<u8>), DispatchError> {
+	let data = (func_id, input).encode();
+	let output = Contracts::bare_call(account(Bob), contract.clone(), 0, GAS_LIMIT, data, false)
+		.result?
+		.data
+		.0;
+	let (status, output) = output.split_at(4);
+	Ok((u32::decode(&mut &status[..]).unwrap(), output.to_vec()))
+}
+
+fn proof_of(contract: &AccountId, claim: &[u8]) -> Option<(AccountId, BlockNumber)> {
+	let (status, output) = call(contract, 1, claim.to_vec()).unwrap();
+	assert_eq!(status, 0);
+	Decode::decode(&mut &output[..]).unwrap()
+}
+
+fn is_owner(contract: &AccountId, claim: &[u8], account: &AccountId) -> bool {
+	let (status, output) = call(contract, 3, (claim.to_vec(), account.clone())).unwrap();
+	assert_eq!(status, 0);
+	Decode::decode(&mut &output[..]).unwrap()
+}
+
+fn create_claim(contract: &AccountId, claim: &[u8]) -> u32 {
+	call(contract, 2, claim.to_vec()).unwrap().0
+}
+
+#[test]
+fn contracts_read_claims() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+		assert_eq!(TemplateModule::do_create_claim(account(Charlie), CLAIM.to_vec()), Ok(()));
+
+		assert_eq!(proof_of(&contract, CLAIM), Some((account(Charlie), 1)));
+		assert_eq!(proof_of(&contract, b"an unclaimed document"), None);
+		assert!(is_owner(&contract, CLAIM, &account(Charlie)));
+		assert!(!is_owner(&contract, CLAIM, &contract));
+	})
+}
+
+#[test]
+fn contracts_create_claims_they_own() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+
+		assert_eq!(create_claim(&contract, CLAIM), 0);
+		assert_eq!(TemplateModule::proof_of(CLAIM), Some((contract.clone(), 1)));
+		assert!(is_owner(&contract, CLAIM, &contract));
+
+		assert_eq!(create_claim(&contract, CLAIM), 1);
+		assert_eq!(create_claim(&contract, b"short"), 2);
+		assert_eq!(create_claim(&contract, &[0; 33]), 3);
+	})
+}
+
+#[test]
+fn unknown_functions_and_invalid_input_trap() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+
+		assert!(call(&contract, 4, CLAIM.to_vec()).is_err());
+		// Not a SCALE encoded claim.
+		assert!(call(&contract, 1, [0xffu8; 3]).is_err());
+	})
+}
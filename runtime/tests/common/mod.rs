@@ -0,0 +1,130 @@
+//! The runtime as the node runs it: built from its `GenesisConfig` and driven block by block
+//! through `Executive`, with extrinsics signed and checked with the real `SignedExtra`.
+
+// Every test file uses a different part of the harness.
+#![allow(dead_code)]
+
+use codec::Encode;
+use node_template_runtime::{
+	opaque::SessionKeys, AccountId, Balance, BalancesConfig, BuildStorage, Call, CouncilConfig,
+	Event, Executive, GenesisConfig, Header, SessionConfig, SignedExtra, SudoConfig, System,
+	SystemConfig, TimestampCall, UncheckedExtrinsic, ValidatorSetConfig, SLOT_DURATION, UNITS,
+};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+use sp_runtime::{
+	generic::{Digest, DigestItem, Era, SignedPayload},
+	traits::Header as _,
+	ApplyExtrinsicResult, DispatchResult,
+};
+
+pub use sp_keyring::Sr25519Keyring::{Alice, Bob, Charlie, Dave, Eve};
+
+/// The free balance of every endowed account at genesis.
+pub const ENDOWMENT: Balance = 1_000 * UNITS;
+
+/// Alice is the only validator, so she authors every block, holds the sudo key and is the
+/// council. Alice, Bob, Charlie and Dave are endowed; Eve has nothing.
+pub fn genesis() -> GenesisConfig {
+	let alice = Alice.to_account_id();
+	GenesisConfig {
+		system: SystemConfig { code: vec![], changes_trie_config: Default::default() },
+		balances: BalancesConfig {
+			balances: [Alice, Bob, Charlie, Dave]
+				.iter()
+				.map(|keyring| (keyring.to_account_id(), ENDOWMENT))
+				.collect(),
+		},
+		vesting: Default::default(),
+		validator_set: ValidatorSetConfig { initial_validators: vec![alice.clone()] },
+		session: SessionConfig {
+			keys: vec![(
+				alice.clone(),
+				alice.clone(),
+				SessionKeys {
+					aura: Sr25519Keyring::Alice.public().into(),
+					grandpa: Ed25519Keyring::Alice.public().into(),
+				},
+			)],
+		},
+		aura: Default::default(),
+		grandpa: Default::default(),
+		sudo: SudoConfig { key: alice.clone() },
+		council: CouncilConfig { members: vec![alice], phantom: Default::default() },
+		democracy: Default::default(),
+		treasury: Default::default(),
+		template_module: Default::default(),
+	}
+}
+
+/// The runtime at `genesis`, with block 1 initialized.
+pub fn new_test_ext_with(genesis: GenesisConfig) -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities = genesis.build_storage().unwrap().into();
+	ext.execute_with(next_block);
+	ext
+}
+
+/// The runtime at the default [`genesis`], with block 1 initialized.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with(genesis())
+}
+
+/// Finalize the current block, if any, and initialize the next one in the next Aura slot, with
+/// its timestamp inherent applied.
+pub fn next_block() {
+	let number = System::block_number();
+	let parent_hash =
+		if number == 0 { System::block_hash(0) } else { Executive::finalize_block().hash() };
+
+	let slot = Slot::from(u64::from(number) + 1);
+	let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+	Executive::initialize_block(&Header::new(
+		number + 1,
+		Default::default(),
+		Default::default(),
+		parent_hash,
+		digest,
+	));
+
+	let now = Call::Timestamp(TimestampCall::set(u64::from(slot) * SLOT_DURATION));
+	assert_eq!(Executive::apply_extrinsic(UncheckedExtrinsic::new_unsigned(now)), Ok(Ok(())));
+}
+
+/// `call` signed by `signer` at its next nonce, immortal and without a tip.
+pub fn sign(signer: Sr25519Keyring, call: Call) -> UncheckedExtrinsic {
+	let nonce = System::account_nonce(signer.to_account_id());
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(Era::Immortal),
+		frame_system::CheckNonce::from(nonce),
+		frame_system::CheckWeight::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::from(0),
+	);
+	let payload = SignedPayload::new(call, extra).unwrap();
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, signer.to_account_id().into(), signature.into(), extra)
+}
+
+/// Apply `xt` in the current block.
+pub fn apply(xt: UncheckedExtrinsic) -> ApplyExtrinsicResult {
+	Executive::apply_extrinsic(xt)
+}
+
+/// Sign `call` by `signer` and apply it, returning the outcome of its dispatch. The transaction
+/// itself must be valid.
+pub fn signed(signer: Sr25519Keyring, call: Call) -> DispatchResult {
+	apply(sign(signer, call)).expect("the transaction is valid")
+}
+
+/// The account of `keyring`.
+pub fn account(keyring: Sr25519Keyring) -> AccountId {
+	keyring.to_account_id()
+}
+
+/// The events deposited in the current block.
+pub fn events() -> Vec<Event> {
+	System::events().into_iter().map(|record| record.event).collect()
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use codec::Encode;
+use common::*;
+use frame_support::weights::{GetDispatchInfo, WeightToFeePolynomial};
+use node_template_runtime::{
+	pallet_template, Balances, Call, ExtrinsicBaseWeight, TemplateModule, TransactionByteFee,
+	TransactionPayment, Treasury, WeightToFee, CENTS,
+};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidityError};
+
+fn create_claim(claim: &[u8]) -> Call {
+	Call::TemplateModule(pallet_template::Call::create_claim(claim.to_vec()))
+}
+
+#[test]
+fn fees_are_base_weight_and_length_fees() {
+	new_test_ext().execute_with(|| {
+		let xt = sign(Bob, create_claim(b"a claimed document"));
+		let len = xt.encoded_size() as u32;
+		let info = xt.get_dispatch_info();
+
+		let base_fee = WeightToFee::calc(&ExtrinsicBaseWeight::get());
+		assert_eq!(base_fee, CENTS / 10);
+		assert_eq!(
+			TransactionPayment::compute_fee(len, &info, 0),
+			base_fee + WeightToFee::calc(&info.weight) + TransactionByteFee::get() * len as u128
+		);
+	})
+}
+
+#[test]
+fn fees_are_charged_and_split_between_treasury_and_author() {
+	new_test_ext().execute_with(|| {
+		let treasury = Balances::free_balance(Treasury::account_id());
+		let xt = sign(Bob, create_claim(b"a claimed document"));
+		let fee =
+			TransactionPayment::compute_fee(xt.encoded_size() as u32, &xt.get_dispatch_info(), 0);
+
+		assert_eq!(apply(xt), Ok(Ok(())));
+
+		let to_treasury = fee * 80 / 100;
+		assert_eq!(Balances::free_balance(account(Bob)), ENDOWMENT - fee);
+		assert_eq!(Balances::free_balance(Treasury::account_id()), treasury + to_treasury);
+		assert_eq!(Balances::free_balance(account(Alice)), ENDOWMENT + fee - to_treasury);
+	})
+}
+
+#[test]
+fn accounts_that_cannot_pay_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			apply(sign(Eve, create_claim(b"a claimed document"))),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+		assert_eq!(TemplateModule::proof_of(b"a claimed document"), None);
+	})
+}
+
+#[test]
+fn nonces_are_checked() {
+	new_test_ext().execute_with(|| {
+		let first = sign(Bob, create_claim(b"first claimed document"));
+		let replayed = first.clone();
+
+		assert_eq!(apply(first), Ok(Ok(())));
+		assert_eq!(
+			apply(replayed),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Stale))
+		);
+	})
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::*;
+use node_template_runtime::{Balances, BasicDeposit, Call, Identity, Runtime};
+use pallet_identity::{Data, IdentityInfo};
+
+fn set_display_name(name: &[u8]) -> Call {
+	Call::Identity(pallet_identity::Call::set_identity(IdentityInfo {
+		additional: Default::default(),
+		display: Data::Raw(name.to_vec()),
+		legal: Data::None,
+		web: Data::None,
+		riot: Data::None,
+		email: Data::None,
+		pgp_fingerprint: None,
+		image: Data::None,
+		twitter: Data::None,
+	}))
+}
+
+#[test]
+fn display_names_reserve_the_basic_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Bob, set_display_name(b"Bob")), Ok(()));
+
+		let registration = Identity::identity(account(Bob)).unwrap();
+		assert_eq!(registration.info.display, Data::Raw(b"Bob".to_vec()));
+		assert_eq!(registration.deposit, BasicDeposit::get());
+		assert_eq!(Balances::reserved_balance(account(Bob)), BasicDeposit::get());
+
+		// Renaming keeps the same deposit.
+		next_block();
+		assert_eq!(signed(Bob, set_display_name(b"Robert")), Ok(()));
+		assert_eq!(Balances::reserved_balance(account(Bob)), BasicDeposit::get());
+	})
+}
+
+#[test]
+fn clearing_a_display_name_returns_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Bob, set_display_name(b"Bob")), Ok(()));
+		let free = Balances::free_balance(account(Bob));
+
+		assert_eq!(signed(Bob, Call::Identity(pallet_identity::Call::clear_identity())), Ok(()));
+
+		assert_eq!(Identity::identity(account(Bob)), None);
+		assert_eq!(Balances::reserved_balance(account(Bob)), 0);
+		assert!(Balances::free_balance(account(Bob)) > free);
+		assert_eq!(
+			signed(Bob, Call::Identity(pallet_identity::Call::clear_identity())),
+			Err(pallet_identity::Error::<Runtime>::NotNamed.into())
+		);
+	})
+}
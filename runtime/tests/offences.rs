@@ -0,0 +1,61 @@
+mod common;
+
+use common::*;
+use finality_grandpa::{Equivocation, Message, Prevote};
+use frame_support::traits::KeyOwnerProofSystem;
+use node_template_runtime::{BlockNumber, Call, Event, Hash, Historical, Runtime, Session};
+use pallet_grandpa::{
+	fg_primitives::{self, EquivocationProof},
+	AuthorityId as GrandpaId,
+};
+use sp_core::H256;
+use sp_keyring::Ed25519Keyring;
+
+/// A prevote for `target` in round 1 of the genesis set, signed with Alice's GRANDPA key.
+fn prevote(target: H256) -> (Prevote<Hash, BlockNumber>, fg_primitives::AuthoritySignature) {
+	let prevote = Prevote { target_hash: target, target_number: 1 };
+	let payload = fg_primitives::localized_payload(1, 0, &Message::Prevote(prevote.clone()));
+	(prevote, Ed25519Keyring::Alice.sign(&payload).into())
+}
+
+/// Alice prevoting for two different blocks in the same round.
+fn report_equivocation() -> Call {
+	let alice: GrandpaId = Ed25519Keyring::Alice.public().into();
+	let proof = EquivocationProof::new(
+		0,
+		fg_primitives::Equivocation::Prevote(Equivocation {
+			round_number: 1,
+			identity: alice.clone(),
+			first: prevote(H256::repeat_byte(1)),
+			second: prevote(H256::repeat_byte(2)),
+		}),
+	);
+	let key_owner_proof = Historical::prove((fg_primitives::KEY_TYPE, alice)).unwrap();
+	Call::Grandpa(pallet_grandpa::Call::report_equivocation(Box::new(proof), key_owner_proof))
+}
+
+#[test]
+fn equivocating_validators_are_disabled() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Bob, report_equivocation()), Ok(()));
+
+		assert!(events()
+			.iter()
+			.any(|event| matches!(event, Event::Offences(pallet_offences::Event::Offence(..)))));
+		assert_eq!(Session::disabled_validators(), vec![0]);
+		// Disabled, not removed.
+		assert_eq!(Session::validators(), vec![account(Alice)]);
+	})
+}
+
+#[test]
+fn equivocations_are_reported_once() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Bob, report_equivocation()), Ok(()));
+		assert_eq!(
+			signed(Charlie, report_equivocation()),
+			Err(pallet_grandpa::Error::<Runtime>::DuplicateOffenceReport.into())
+		);
+		assert_eq!(Session::disabled_validators(), vec![0]);
+	})
+}
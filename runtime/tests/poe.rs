@@ -0,0 +1,67 @@
+mod common;
+
+use common::*;
+use node_template_runtime::{pallet_template, Balances, Call, Event, Runtime, TemplateModule};
+use pallet_template::{Error, Event as PoeEvent};
+use sp_keyring::Sr25519Keyring;
+
+const CLAIM: &[u8] = b"a claimed document";
+
+fn create_claim(claim: &[u8]) -> Call {
+	Call::TemplateModule(pallet_template::Call::create_claim(claim.to_vec()))
+}
+
+fn revoke_claim(claim: &[u8]) -> Call {
+	Call::TemplateModule(pallet_template::Call::revoke_claim(claim.to_vec()))
+}
+
+fn transfer_claim(claim: &[u8], dest: Sr25519Keyring) -> Call {
+	Call::TemplateModule(pallet_template::Call::transfer_clain(claim.to_vec(), account(dest)))
+}
+
+#[test]
+fn claims_are_created_through_signed_extrinsics() {
+	new_test_ext().execute_with(|| {
+		next_block();
+		assert_eq!(signed(Bob, create_claim(CLAIM)), Ok(()));
+
+		assert_eq!(TemplateModule::proof_of(CLAIM), Some((account(Bob), 2)));
+		assert!(events().contains(&Event::TemplateModule(PoeEvent::ClaimCreated(
+			account(Bob),
+			CLAIM.to_vec()
+		))));
+	})
+}
+
+#[test]
+fn failed_claims_still_pay_fees() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Bob, create_claim(CLAIM)), Ok(()));
+
+		assert_eq!(
+			signed(Charlie, create_claim(CLAIM)),
+			Err(Error::<Runtime>::ProofAlreadyClaimed.into())
+		);
+		assert_eq!(signed(Charlie, create_claim(b"short")), Err(Error::<Runtime>::TooShort.into()));
+		assert_eq!(signed(Charlie, create_claim(&[0; 33])), Err(Error::<Runtime>::TooLong.into()));
+
+		assert_eq!(TemplateModule::proof_of(CLAIM), Some((account(Bob), 1)));
+		assert!(Balances::free_balance(account(Charlie)) < ENDOWMENT);
+	})
+}
+
+#[test]
+fn claims_are_transferred_and_revoked_by_their_owner() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Bob, create_claim(CLAIM)), Ok(()));
+		next_block();
+
+		assert_eq!(signed(Bob, transfer_claim(CLAIM, Charlie)), Ok(()));
+		assert_eq!(TemplateModule::proof_of(CLAIM), Some((account(Charlie), 2)));
+
+		assert_eq!(signed(Bob, revoke_claim(CLAIM)), Err(Error::<Runtime>::NotProofOwner.into()));
+		assert_eq!(signed(Charlie, revoke_claim(CLAIM)), Ok(()));
+		assert_eq!(TemplateModule::proof_of(CLAIM), None);
+		assert_eq!(signed(Charlie, revoke_claim(CLAIM)), Err(Error::<Runtime>::NoSuchProof.into()));
+	})
+}
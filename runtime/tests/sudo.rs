@@ -0,0 +1,39 @@
+mod common;
+
+use common::*;
+use frame_support::storage::migration;
+use node_template_runtime::Call;
+use sp_io::hashing::twox_128;
+
+fn sudo(call: Call) -> Call {
+	Call::Sudo(pallet_sudo::Call::sudo(Box::new(call)))
+}
+
+fn remark() -> Call {
+	Call::System(frame_system::Call::remark(b"still sudo".to_vec()))
+}
+
+/// Drops the sudo key, as governance does to hand over control.
+fn kill_sudo_key() -> Call {
+	let key = [twox_128(b"Sudo"), twox_128(b"Key")].concat();
+	Call::System(frame_system::Call::kill_storage(vec![key]))
+}
+
+#[test]
+fn sudo_is_dropped_by_killing_its_key() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Alice, sudo(remark())), Ok(()));
+		assert_eq!(signed(Alice, sudo(kill_sudo_key())), Ok(()));
+		assert!(!migration::have_storage_value(b"Sudo", b"Key", &[]));
+
+		assert!(signed(Alice, sudo(remark())).is_err());
+	})
+}
+
+#[test]
+fn other_calls_pass_the_filter_without_sudo() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(signed(Alice, sudo(kill_sudo_key())), Ok(()));
+		assert_eq!(signed(Bob, remark()), Ok(()));
+	})
+}
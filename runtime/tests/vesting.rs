@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use node_template_runtime::{
+	pallet_template, Balances, BalancesCall, BlockNumber, Call, PoeCommitmentDeposit, Runtime,
+	Vesting, VestingConfig, UNITS,
+};
+use sp_runtime::{
+	traits::{BlakeTwo256, Hash},
+	DispatchResult,
+};
+
+/// The blocks Dave's endowment vests over, from genesis.
+const VESTING_BLOCKS: BlockNumber = 10;
+
+/// The default genesis, with all of Dave's endowment vesting over `VESTING_BLOCKS` from block
+/// `begin`.
+fn new_vesting_ext(begin: BlockNumber) -> sp_io::TestExternalities {
+	new_test_ext_with(node_template_runtime::GenesisConfig {
+		vesting: VestingConfig { vesting: vec![(account(Dave), begin, VESTING_BLOCKS, 0)] },
+		..genesis()
+	})
+}
+
+fn transfer(amount: u128) -> Call {
+	Call::Balances(BalancesCall::transfer(account(Eve).into(), amount))
+}
+
+fn vest() -> Call {
+	Call::Vesting(pallet_vesting::Call::vest())
+}
+
+fn commit(salt: &[u8]) -> Call {
+	let commitment = BlakeTwo256::hash(&[&b"a claimed document"[..], salt].concat());
+	Call::TemplateModule(pallet_template::Call::commit_claim(commitment))
+}
+
+#[test]
+fn vesting_releases_funds_across_blocks() {
+	new_vesting_ext(0).execute_with(|| {
+		let per_block = ENDOWMENT / u128::from(VESTING_BLOCKS);
+		assert_eq!(Vesting::vesting_balance(&account(Dave)), Some(ENDOWMENT - per_block));
+		assert_eq!(
+			signed(Dave, transfer(per_block)),
+			Err(pallet_balances::Error::<Runtime>::LiquidityRestrictions.into())
+		);
+
+		for _ in 1..4 {
+			next_block();
+		}
+		assert_eq!(Vesting::vesting_balance(&account(Dave)), Some(ENDOWMENT - 4 * per_block));
+		assert_eq!(signed(Dave, vest()), Ok(()));
+		assert_eq!(signed(Dave, transfer(3 * per_block)), Ok(()));
+		assert_eq!(
+			signed(Dave, transfer(per_block)),
+			Err(pallet_balances::Error::<Runtime>::LiquidityRestrictions.into())
+		);
+
+		for _ in 4..VESTING_BLOCKS {
+			next_block();
+		}
+		assert_eq!(signed(Dave, vest()), Ok(()));
+		assert_eq!(Vesting::vesting_balance(&account(Dave)), None);
+		assert_eq!(signed(Dave, transfer(per_block)), Ok(()));
+	})
+}
+
+#[test]
+fn commitment_deposits_come_from_vested_funds_only() {
+	new_vesting_ext(5).execute_with(|| {
+		let per_block = ENDOWMENT / u128::from(VESTING_BLOCKS);
+		let insufficient: DispatchResult =
+			Err(pallet_template::Error::<Runtime>::InsufficientBalance.into());
+
+		// Nothing has vested yet.
+		assert_eq!(signed(Dave, commit(b"early")), insufficient);
+		assert_eq!(Balances::reserved_balance(account(Dave)), 0);
+
+		// Two blocks' worth has vested by block 7, but stays locked as `vest` was not called.
+		for _ in 1..7 {
+			next_block();
+		}
+		assert_eq!(Vesting::vesting_balance(&account(Dave)), Some(ENDOWMENT - 2 * per_block));
+		assert_eq!(Balances::usable_balance(account(Dave)), 0);
+		assert_eq!(signed(Dave, commit(b"vested")), Ok(()));
+		assert_eq!(Balances::reserved_balance(account(Dave)), PoeCommitmentDeposit::get());
+
+		// Once the vested funds are spent, what is still vesting cannot be reserved.
+		let usable = Balances::usable_balance(account(Dave));
+		assert_eq!(signed(Dave, transfer(usable - UNITS)), Ok(()));
+		assert_eq!(signed(Dave, commit(b"unvested")), insufficient);
+		assert_eq!(Balances::reserved_balance(account(Dave)), PoeCommitmentDeposit::get());
+	})
+}